@@ -3,8 +3,13 @@ use std::fs;
 use std::path::Path;
 
 use crate::cli::{GenerateParams, ImageFormat};
+use crate::colors::{MaterialVariant, ThemeVariant};
 use crate::config::Config;
+use crate::contrast::ContrastMode;
 use crate::generate::do_generate;
+use crate::images::{CropMode, Effect, ProceduralBackground, Stylize};
+use crate::legibility::Legibility;
+use crate::rotation;
 
 /// Convert a PNG file to WebP format, removing the original PNG
 pub fn convert_to_webp(png_path: &Path) -> Result<std::path::PathBuf> {
@@ -77,8 +82,146 @@ pub fn run_build(
             opt.unwrap_or_else(|| defaults.get(key).and_then(|v| v.as_bool()).unwrap_or(def))
         };
 
+        // Brand profile: the post's values win over it, it wins over [defaults]
+        let kit = match cfg.post_kit(post) {
+            Ok(k) => k,
+            Err(e) => {
+                println!("\n  ✗ {}: {}", name, e);
+                continue;
+            }
+        };
+        let branded = |opt: &Option<String>, from_kit: &Option<String>, key: &str| {
+            cfg.branded(opt, from_kit, key)
+        };
+
+        // An explicit focal point wins over the crop mode
+        let crop = match post.focus {
+            Some([x, y]) => Some(CropMode::Focus(x, y)),
+            None => match post.crop.clone().or_else(|| {
+                defaults
+                    .get("crop")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+            }) {
+                Some(c) => match c.parse::<CropMode>() {
+                    Ok(mode) => Some(mode),
+                    Err(e) => {
+                        println!("\n  ✗ {}: {}", name, e);
+                        continue;
+                    }
+                },
+                None => None,
+            },
+        };
+
+        let effect_specs: Vec<String> = post.effects.clone().unwrap_or_else(|| {
+            defaults
+                .get("effects")
+                .and_then(|v| v.as_array())
+                .map(|a| {
+                    a.iter()
+                        .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                        .collect()
+                })
+                .unwrap_or_default()
+        });
+        let effects = match effect_specs
+            .iter()
+            .map(|e| e.parse::<Effect>())
+            .collect::<Result<Vec<_>>>()
+        {
+            Ok(fx) => fx,
+            Err(e) => {
+                println!("\n  ✗ {}: {}", name, e);
+                continue;
+            }
+        };
+
+        let background = match get_str(&post.background, "background", "").as_str() {
+            "" | "none" => None,
+            kind => match kind.parse::<ProceduralBackground>() {
+                Ok(b) => Some(b),
+                Err(e) => {
+                    println!("\n  ✗ {}: {}", name, e);
+                    continue;
+                }
+            },
+        };
+
+        let seed = match post
+            .seed
+            .or_else(|| defaults.get("seed").and_then(|v| v.as_integer()))
+        {
+            Some(v) => match u64::try_from(v) {
+                Ok(s) => Some(s),
+                Err(_) => {
+                    println!("\n  ✗ {}: Invalid seed {} (must not be negative)", name, v);
+                    continue;
+                }
+            },
+            None => None,
+        };
+
+        let stylize = match get_str(&post.stylize, "stylize", "").as_str() {
+            "" | "none" => None,
+            style => match style.parse::<Stylize>() {
+                Ok(s) => Some(s),
+                Err(e) => {
+                    println!("\n  ✗ {}: {}", name, e);
+                    continue;
+                }
+            },
+        };
+
+        let contrast = match get_str(&post.contrast, "contrast", "").as_str() {
+            "" => None,
+            mode => match mode.parse::<ContrastMode>() {
+                Ok(m) => Some(m),
+                Err(e) => {
+                    println!("\n  ✗ {}: {}", name, e);
+                    continue;
+                }
+            },
+        };
+
+        let legibility = match get_str(&post.legibility, "legibility", "").as_str() {
+            "" => None,
+            mode => match mode.parse::<Legibility>() {
+                Ok(m) => Some(m),
+                Err(e) => {
+                    println!("\n  ✗ {}: {}", name, e);
+                    continue;
+                }
+            },
+        };
+
+        let variant = match get_str(&post.variant, "variant", "").as_str() {
+            "" => None,
+            v => match v.parse::<ThemeVariant>() {
+                Ok(v) => Some(v),
+                Err(e) => {
+                    println!("\n  ✗ {}: {}", name, e);
+                    continue;
+                }
+            },
+        };
+
+        let material_variant =
+            match get_str(&post.material_variant, "material_variant", "").as_str() {
+                "" => None,
+                v => match v.parse::<MaterialVariant>() {
+                    Ok(v) => Some(v),
+                    Err(e) => {
+                        println!("\n  ✗ {}: {}", name, e);
+                        continue;
+                    }
+                },
+            };
+
+        let accent =
+            branded(&post.accent, &kit.accent, "accent").unwrap_or_else(|| "theme".to_string());
         let params = GenerateParams {
-            brand: get_str(&post.brand, "brand", "Presuposicionalismo"),
+            brand: branded(&post.brand, &kit.brand, "brand").unwrap_or_default(),
             title: get_str(&post.title, "title", ""),
             quote: get_str(&post.quote, "quote", ""),
             image: post.image.clone().or_else(|| {
@@ -87,28 +230,17 @@ pub fn run_build(
                     .and_then(|v| v.as_str())
                     .map(|s| s.to_string())
             }),
-            logo: post.logo.clone().or_else(|| {
-                defaults
-                    .get("logo")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string())
-            }),
-            overlay: post
-                .overlay
-                .clone()
-                .or_else(|| {
-                    defaults
-                        .get("overlay")
-                        .and_then(|v| v.as_str())
-                        .map(|s| s.to_string())
-                })
+            logo: branded(&post.logo, &kit.logo, "logo"),
+            overlay: branded(&post.overlay, &kit.overlay, "overlay")
                 .or(Some("assets/Solid-bg.svg".to_string())),
-            accent: get_str(&post.accent, "accent", "theme"),
-            auto_accent: get_str(&post.accent, "accent", "theme") == "auto",
-            url: get_str(&post.url, "url", "Presuposicionalismo.com"),
+            auto_accent: accent == "auto",
+            accent,
+            url: branded(&post.url, &kit.url, "url").unwrap_or_default(),
             platform: get_str(&post.platform, "platform", "instagram-post"),
             layout: get_str(&post.layout, "layout", "article"),
-            theme: get_str(&post.theme, "theme", "dark"),
+            theme: branded(&post.theme, &kit.theme, "theme").unwrap_or_else(|| "dark".to_string()),
+            post_index: i,
+            date: post.date.clone().or_else(|| cfg.default_date()),
             author: get_str(&post.author, "author", ""),
             source: post.source.clone().or_else(|| {
                 defaults
@@ -122,34 +254,39 @@ pub fn run_build(
                     .and_then(|v| v.as_str())
                     .map(|s| s.to_string())
             }),
-            slides: post.slides.as_ref().map(|s| s.join("|")),
+            slides: post.slides.clone().unwrap_or_default(),
             contour: get_bool(post.contour, "contour", false),
-            font_heading: post.font_heading.clone().or_else(|| {
-                defaults
-                    .get("font-heading")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string())
-            }),
-            font_body: post.font_body.clone().or_else(|| {
-                defaults
-                    .get("font-body")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string())
-            }),
-            font_mono: post.font_mono.clone().or_else(|| {
-                defaults
-                    .get("font-mono")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string())
-            }),
+            crop,
+            effects,
+            background,
+            seed,
+            stylize,
+            contrast,
+            legibility,
+            variant,
+            material_variant,
+            palette_engine: get_str(&post.palette_engine, "palette_engine", "")
+                .split(',')
+                .map(|s| s.to_string())
+                .collect(),
+            font_heading: branded(&post.font_heading, &kit.font_heading, "font-heading"),
+            font_body: branded(&post.font_body, &kit.font_body, "font-body"),
+            font_mono: branded(&post.font_mono, &kit.font_mono, "font-mono"),
         };
 
         println!("\n  ⟩ {} — {}", name, params.title);
 
         if dry_run {
+            let theme = match rotation::theme_for(&params.theme, i, params.date.as_deref()) {
+                Ok(t) => t,
+                Err(e) => {
+                    println!("    ✗ {}", e);
+                    continue;
+                }
+            };
             println!(
                 "    (dry run) layout={} platform={} theme={}",
-                params.layout, params.platform, params.theme
+                params.layout, params.platform, theme
             );
             continue;
         }
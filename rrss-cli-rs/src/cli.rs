@@ -1,6 +1,13 @@
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
+use crate::colors::{Harmony, MaterialVariant, ThemeVariant};
+use crate::config::Slide;
+use crate::contrast::ContrastMode;
+use crate::cvd::Cvd;
+use crate::images::{CropMode, Effect, ProceduralBackground, Stylize};
+use crate::legibility::Legibility;
+
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
@@ -17,6 +24,25 @@ pub enum Commands {
         format: OutputFormat,
         #[arg(short, long, default_value = "custom")]
         name: String,
+        /// Archivo de salida (obligatorio para ase y png, por defecto <name>.<ext>)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Armonía cromática: complementary, analogous, triadic, split, tetradic o monochrome
+        #[arg(long)]
+        harmony: Option<Harmony>,
+        /// Guarda además un muestrario PNG de la paleta
+        #[arg(long)]
+        preview: Option<PathBuf>,
+        /// Ajustes del motor de paletas: un preset (default, vivid, muted, high-contrast, deep)
+        /// y/o valores clave=valor, separados por comas (ej. "vivid,dark.surface_distance=0.08")
+        #[arg(long, value_delimiter = ',')]
+        palette_engine: Vec<String>,
+        /// Verificación de contraste de la paleta: off, warn, fix o strict
+        #[arg(long, default_value = "fix")]
+        contrast: ContrastMode,
+        /// posts.toml con la sección [palette_engine]
+        #[arg(long, default_value = "posts.toml")]
+        config: PathBuf,
     },
 
     /// Extrae colores de una imagen
@@ -30,52 +56,50 @@ pub enum Commands {
         suggest_accent: bool,
         #[arg(short, long, default_value = "extracted")]
         name: String,
-    },
-
-    /// Genera main.typ (comando único)
-    Generate {
-        #[arg(short, long, default_value = "Presuposicionalismo")]
-        brand: String,
-        #[arg(short, long)]
-        title: String,
+        /// Formato de la paleta derivada con --format palette
+        #[arg(long, default_value = "table")]
+        palette_format: OutputFormat,
+        /// Archivo de salida de la paleta o del análisis JSON
         #[arg(short, long)]
-        quote: String,
+        output: Option<PathBuf>,
+        /// Ajustes del motor de paletas: un preset (default, vivid, muted, high-contrast, deep)
+        /// y/o valores clave=valor, separados por comas (ej. "vivid,dark.surface_distance=0.08")
+        #[arg(long, value_delimiter = ',')]
+        palette_engine: Vec<String>,
+        /// Resta peso a las zonas de texto de este layout al extraer los colores
         #[arg(short, long)]
-        image: Option<String>,
-        #[arg(long)]
-        logo: Option<String>,
-        #[arg(long)]
-        overlay: Option<String>,
-        #[arg(short, long, default_value = "theme")]
-        accent: String,
-        #[arg(long)]
-        auto_accent: bool,
-        #[arg(short, long, default_value = "Presuposicionalismo.com")]
-        url: String,
+        layout: Option<String>,
+        /// Plataforma para la que se calculan las zonas de texto de --layout
         #[arg(short, long, default_value = "instagram-post")]
         platform: String,
-        #[arg(short, long, default_value = "article")]
-        layout: String,
-        #[arg(long, default_value = "dark")]
-        theme: String,
-        #[arg(long, default_value = "")]
-        author: String,
+        /// Tiene en cuenta los píxeles casi negros y casi blancos
         #[arg(long)]
-        source: Option<String>,
-        #[arg(long)]
-        tag: Option<String>,
+        keep_extremes: bool,
+        /// Verificación de contraste de la paleta: off, warn, fix o strict
+        #[arg(long, default_value = "fix")]
+        contrast: ContrastMode,
+        /// posts.toml con la sección [palette_engine]
+        #[arg(long, default_value = "posts.toml")]
+        config: PathBuf,
+    },
+
+    /// Gestiona los temas
+    Themes {
+        #[command(subcommand)]
+        command: ThemeCommands,
+    },
+
+    /// Genera main.typ (comando único)
+    Generate {
+        #[command(flatten)]
+        post: PostArgs,
+        /// Textos de las slides del carrusel separados por "|" (texto plano, no código Typst)
         #[arg(long)]
         slides: Option<String>,
         #[arg(long)]
         contour: bool,
         #[arg(short, long, default_value = "main.typ")]
         output: String,
-        #[arg(long)]
-        font_heading: Option<String>,
-        #[arg(long)]
-        font_body: Option<String>,
-        #[arg(long)]
-        font_mono: Option<String>,
     },
 
     /// Compila archivos .typ a PNG/WebP
@@ -94,48 +118,34 @@ pub enum Commands {
         format: ImageFormat,
     },
 
+    /// Simula deficiencias de visión del color sobre los PNG y la paleta del tema
+    Proof {
+        /// Imágenes a simular (por defecto, los PNG de output/)
+        #[arg(num_args = 0..)]
+        files: Vec<PathBuf>,
+        /// Deficiencias: protan, deutan, tritan, achroma (todas por defecto)
+        #[arg(long, value_delimiter = ',')]
+        cvd: Vec<Cvd>,
+        /// Intensidad de la deficiencia, de 0 a 1
+        #[arg(long, default_value_t = 1.0)]
+        severity: f32,
+        /// Tema cuya paleta se revisa (por defecto el de [defaults])
+        #[arg(long)]
+        theme: Option<String>,
+        #[arg(long, default_value = "posts.toml")]
+        config: PathBuf,
+        #[arg(short = 'd', long, default_value = "output/proof")]
+        output_dir: PathBuf,
+    },
+
     /// Pipeline completo: genera y compila
     Full {
-        #[arg(short, long, default_value = "Presuposicionalismo")]
-        brand: String,
-        #[arg(short, long)]
-        title: String,
-        #[arg(short, long)]
-        quote: String,
-        #[arg(short, long)]
-        image: Option<String>,
-        #[arg(long)]
-        logo: Option<String>,
-        #[arg(long)]
-        overlay: Option<String>,
-        #[arg(short, long, default_value = "theme")]
-        accent: String,
-        #[arg(long)]
-        auto_accent: bool,
-        #[arg(short, long, default_value = "Presuposicionalismo.com")]
-        url: String,
-        #[arg(short, long, default_value = "instagram-post")]
-        platform: String,
-        #[arg(short, long, default_value = "article")]
-        layout: String,
-        #[arg(long, default_value = "dark")]
-        theme: String,
-        #[arg(long, default_value = "")]
-        author: String,
-        #[arg(long)]
-        source: Option<String>,
-        #[arg(long)]
-        tag: Option<String>,
+        #[command(flatten)]
+        post: PostArgs,
         #[arg(long, default_value_t = 144)]
         ppi: u32,
         #[arg(short, long, default_value = "main")]
         output_name: String,
-        #[arg(long)]
-        font_heading: Option<String>,
-        #[arg(long)]
-        font_body: Option<String>,
-        #[arg(long)]
-        font_mono: Option<String>,
         #[arg(long, default_value = "png")]
         format: ImageFormat,
     },
@@ -153,11 +163,107 @@ pub enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+pub enum ThemeCommands {
+    /// Exporta un tema a otros formatos (CSS, SCSS, Tailwind, GIMP, Adobe, base16, PNG)
+    Export {
+        name: String,
+        #[arg(short, long, default_value = "css")]
+        format: OutputFormat,
+        /// Archivo de salida (obligatorio para ase y png, por defecto <name>.<ext>)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// posts.toml con temas propios
+        #[arg(long, default_value = "posts.toml")]
+        config: PathBuf,
+    },
+    /// Lista los temas disponibles: incluidos, de themes/ y de posts.toml
+    List {
+        #[arg(long, default_value = "posts.toml")]
+        config: PathBuf,
+    },
+    /// Muestra los colores de un tema y su contraste
+    Show {
+        name: String,
+        #[arg(long, default_value = "posts.toml")]
+        config: PathBuf,
+    },
+    /// Renderiza un post de muestra en cada tema y los reúne en una hoja de contactos
+    Preview {
+        /// Temas a incluir (todos por defecto)
+        #[arg(long, value_delimiter = ',')]
+        themes: Vec<String>,
+        #[arg(short, long, default_value = "article")]
+        layout: String,
+        #[arg(short, long, default_value = "instagram-post")]
+        platform: String,
+        /// Imagen de fondo del post de muestra
+        #[arg(short, long)]
+        image: Option<String>,
+        /// Columnas de la hoja de contactos
+        #[arg(long, default_value_t = 4)]
+        columns: u32,
+        #[arg(long, default_value_t = 72)]
+        ppi: u32,
+        #[arg(long, default_value = "posts.toml")]
+        config: PathBuf,
+        #[arg(short = 'd', long, default_value = "output/themes")]
+        output_dir: PathBuf,
+    },
+    /// Compara dos temas: diferencia de color (ΔE CIEDE2000) de cada rol
+    Diff {
+        a: String,
+        b: String,
+        #[arg(long, default_value = "posts.toml")]
+        config: PathBuf,
+    },
+    /// Interpola dos temas en Oklch e imprime el TOML de la paleta intermedia
+    Mix {
+        a: String,
+        b: String,
+        /// Posición entre los dos temas: 0 es el primero y 1 el segundo
+        #[arg(long = "t", default_value_t = 0.5)]
+        t: f32,
+        /// Genera una serie de N temas de un extremo al otro en lugar de uno solo
+        #[arg(long)]
+        steps: Option<u32>,
+        /// Nombre del tema (por defecto <a>-<b>-<porcentaje>); con --steps, prefijo de la serie
+        #[arg(long)]
+        name: Option<String>,
+        /// Archivo de salida (por defecto la salida estándar)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long, default_value = "posts.toml")]
+        config: PathBuf,
+    },
+    /// Crea un tema oscuro y uno claro con los colores de un logo (SVG o imagen)
+    /// e imprime el TOML para [themes]
+    FromLogo {
+        logo: PathBuf,
+        /// Prefijo de los temas (por defecto el nombre del archivo): <name>-dark y <name>-light
+        #[arg(long)]
+        name: Option<String>,
+        /// Número de colores a extraer del logo
+        #[arg(short, long, default_value_t = 6)]
+        count: u8,
+        /// Archivo de salida (por defecto la salida estándar)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
 #[derive(ValueEnum, Clone)]
 pub enum OutputFormat {
     Table,
     Json,
     Typst,
+    Css,
+    Scss,
+    Tailwind,
+    Gpl,
+    Ase,
+    Base16,
+    Png,
 }
 
 #[derive(ValueEnum, Clone)]
@@ -173,6 +279,92 @@ pub enum ImageFormat {
     Webp,
 }
 
+/// The post options `generate` and `full` share.
+#[derive(Args)]
+pub struct PostArgs {
+    #[arg(short, long)]
+    pub brand: Option<String>,
+    /// Kit de marca de [brands] en posts.toml (marca, URL, logo, tema, fuentes…)
+    #[arg(long)]
+    pub brand_kit: Option<String>,
+    /// posts.toml con los kits de marca, los temas propios y [palette_engine]
+    #[arg(long, default_value = "posts.toml")]
+    pub config: PathBuf,
+    #[arg(short, long)]
+    pub title: String,
+    #[arg(short, long)]
+    pub quote: String,
+    #[arg(short, long)]
+    pub image: Option<String>,
+    #[arg(long)]
+    pub logo: Option<String>,
+    #[arg(long)]
+    pub overlay: Option<String>,
+    #[arg(short, long)]
+    pub accent: Option<String>,
+    #[arg(long)]
+    pub auto_accent: bool,
+    #[arg(short, long)]
+    pub url: Option<String>,
+    #[arg(short, long, default_value = "instagram-post")]
+    pub platform: String,
+    #[arg(short, long, default_value = "article")]
+    pub layout: String,
+    #[arg(long)]
+    pub theme: Option<String>,
+    /// Posición del post en la serie, para theme = "rotate:[...]"
+    #[arg(long, default_value_t = 0)]
+    pub index: usize,
+    /// Fecha de publicación (YYYY-MM-DD[THH:MM]) para las rotaciones por fecha
+    #[arg(long)]
+    pub date: Option<String>,
+    #[arg(long, default_value = "")]
+    pub author: String,
+    #[arg(long)]
+    pub source: Option<String>,
+    #[arg(long)]
+    pub tag: Option<String>,
+    /// Recorta la imagen a la proporción de la plataforma: auto, center o "x,y"
+    #[arg(long)]
+    pub crop: Option<CropMode>,
+    /// Efectos para el fondo, separados por comas (ej. "blur:8,vignette:0.4")
+    #[arg(long, value_delimiter = ',')]
+    pub effects: Vec<Effect>,
+    /// Fondo procedural si no hay imagen: mesh, noise, geometric, halftone
+    #[arg(long)]
+    pub background: Option<ProceduralBackground>,
+    /// Semilla del fondo procedural (por defecto se deriva del título)
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Filtro de estilo con los colores del tema: halftone, posterize[:N], riso
+    #[arg(long)]
+    pub stylize: Option<Stylize>,
+    /// Verificación de contraste del tema: off, warn, fix o strict
+    /// (por defecto fix para temas automáticos y off para el resto)
+    #[arg(long)]
+    pub contrast: Option<ContrastMode>,
+    /// Legibilidad del texto sobre la imagen: off, warn, scrim o variant
+    #[arg(long)]
+    pub legibility: Option<Legibility>,
+    /// Variante de los temas automáticos: dark, light o auto (según la luminosidad de la imagen)
+    #[arg(long)]
+    pub variant: Option<ThemeVariant>,
+    /// Esquema Material 3 de auto-matugen: tonal-spot, vibrant, expressive, fidelity…
+    #[arg(long)]
+    pub material_variant: Option<MaterialVariant>,
+    /// Ajustes del motor de paletas: un preset (default, vivid, muted, high-contrast, deep)
+    /// y/o valores clave=valor, separados por comas (ej. "vivid,dark.surface_distance=0.08")
+    #[arg(long, value_delimiter = ',')]
+    pub palette_engine: Vec<String>,
+    #[arg(long)]
+    pub font_heading: Option<String>,
+    #[arg(long)]
+    pub font_body: Option<String>,
+    #[arg(long)]
+    pub font_mono: Option<String>,
+}
+
+#[derive(Clone)]
 pub struct GenerateParams {
     pub brand: String,
     pub title: String,
@@ -186,11 +378,25 @@ pub struct GenerateParams {
     pub platform: String,
     pub layout: String,
     pub theme: String,
+    /// Position of the post in its series, for `rotate:` themes.
+    pub post_index: usize,
+    /// Publication date (`YYYY-MM-DD[THH:MM]`), required by date-based rotations.
+    pub date: Option<String>,
     pub author: String,
     pub source: Option<String>,
     pub tag: Option<String>,
-    pub slides: Option<String>,
+    pub slides: Vec<Slide>,
     pub contour: bool,
+    pub crop: Option<CropMode>,
+    pub effects: Vec<Effect>,
+    pub background: Option<ProceduralBackground>,
+    pub seed: Option<u64>,
+    pub stylize: Option<Stylize>,
+    pub contrast: Option<ContrastMode>,
+    pub legibility: Option<Legibility>,
+    pub variant: Option<ThemeVariant>,
+    pub material_variant: Option<MaterialVariant>,
+    pub palette_engine: Vec<String>,
     pub font_heading: Option<String>,
     pub font_body: Option<String>,
     pub font_mono: Option<String>,
@@ -1,23 +1,181 @@
 // use image::GenericImageView;
-use anyhow::Result;
-use palette::{FromColor, Hsl, OklabHue, Srgb};
+use anyhow::{anyhow, Result};
+use palette::{Clamp, FromColor, Hsl, Lab, Oklab, OklabHue, Oklch, Srgb, Srgba, WithAlpha};
 use quantette::ImageRef;
-use std::path::Path;
+use std::str::FromStr;
 use thaimeleon_lib::scheme_builder::{ChromaBuilder, SchemeBuilder, ThemeConfig};
 
-pub fn hex_to_rgb_tuple(hex_color: &str) -> (u8, u8, u8) {
-    let hex_color = hex_color.trim_start_matches('#');
-    let r = u8::from_str_radix(&hex_color[0..2], 16).unwrap_or(0);
-    let g = u8::from_str_radix(&hex_color[2..4], 16).unwrap_or(0);
-    let b = u8::from_str_radix(&hex_color[4..6], 16).unwrap_or(0);
-    (r, g, b)
+use crate::images;
+
+/// Parse any colour `parse_color` accepts into its RGB channels, ignoring alpha.
+pub fn hex_to_rgb_tuple(color: &str) -> Result<(u8, u8, u8)> {
+    let c = parse_rgb(color)?;
+    Ok((c.red, c.green, c.blue))
 }
 
-pub fn hex_to_hsl(hex_color: &str) -> (f32, f32, f32) {
-    let (r, g, b) = hex_to_rgb_tuple(hex_color);
+pub fn hex_to_hsl(hex_color: &str) -> Result<(f32, f32, f32)> {
+    let (r, g, b) = hex_to_rgb_tuple(hex_color)?;
     let srgb = Srgb::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
     let hsl = Hsl::from_color(srgb);
-    (hsl.hue.into_degrees(), hsl.saturation, hsl.lightness)
+    Ok((hsl.hue.into_degrees(), hsl.saturation, hsl.lightness))
+}
+
+/// Parse a colour written as `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa` (the `#` is
+/// optional for the six and eight digit forms), `rgb()`/`rgba()`, `hsl()`/`hsla()`,
+/// `oklch()` or a CSS named colour. Function arguments may be separated by commas or
+/// spaces, with the alpha after a `/`.
+pub fn parse_color(input: &str) -> Result<Srgba<u8>> {
+    let s = input.trim().to_ascii_lowercase();
+    let parsed = if let Some(hex) = s.strip_prefix('#') {
+        parse_hex_digits(hex)
+    } else if let Some((func, args)) = s.strip_suffix(')').and_then(|f| f.split_once('(')) {
+        parse_color_function(func.trim(), args)
+    } else if let Some(named) = palette::named::from_str(&s) {
+        Some(named.with_alpha(255))
+    } else if s.len() == 6 || s.len() == 8 {
+        parse_hex_digits(&s)
+    } else {
+        None
+    };
+    parsed.ok_or_else(|| {
+        anyhow!(
+            "Invalid color '{}' (expected #rgb, #rrggbb, #rrggbbaa, rgb(), hsl(), oklch() or a CSS color name)",
+            input.trim()
+        )
+    })
+}
+
+/// `parse_color` without the alpha channel.
+pub fn parse_rgb(input: &str) -> Result<Srgb<u8>> {
+    Ok(parse_color(input)?.color)
+}
+
+/// `#rrggbb`, or `#rrggbbaa` when the colour is not opaque.
+pub fn to_hex(c: Srgba<u8>) -> String {
+    if c.alpha == 255 {
+        format!("#{:02x}{:02x}{:02x}", c.red, c.green, c.blue)
+    } else {
+        format!("#{:02x}{:02x}{:02x}{:02x}", c.red, c.green, c.blue, c.alpha)
+    }
+}
+
+/// Parse `input` and write it back as hex, the form the Typst templates receive.
+pub fn normalize_color(input: &str) -> Result<String> {
+    Ok(to_hex(parse_color(input)?))
+}
+
+/// Parse a colour into Oklab, or `None` when it is not valid.
+pub fn hex_oklab(hex: &str) -> Option<Oklab> {
+    let c = parse_rgb(hex).ok()?;
+    Some(Oklab::from_color(c.into_format::<f32>()))
+}
+
+/// Euclidean distance between two Oklab colours.
+pub fn oklab_distance(a: Oklab, b: Oklab) -> f32 {
+    oklab_distance2(a, b).sqrt()
+}
+
+/// Round to three decimals for JSON output, going through f64 so no float noise remains.
+pub fn round3(v: f32) -> f64 {
+    (v as f64 * 1000.0).round() / 1000.0
+}
+
+/// CIEDE2000 colour difference between two sRGB colours.
+pub fn ciede2000(a: Srgb<f32>, b: Srgb<f32>) -> f32 {
+    use palette::color_difference::Ciede2000;
+    Lab::from_color(a).difference(Lab::from_color(b))
+}
+
+fn parse_hex_digits(hex: &str) -> Option<Srgba<u8>> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digits: Vec<u8> = match hex.len() {
+        // Short forms repeat each digit
+        3 | 4 => hex
+            .chars()
+            .map(|c| {
+                let d = c.to_digit(16).unwrap_or(0) as u8;
+                d * 16 + d
+            })
+            .collect(),
+        6 | 8 => (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or(0))
+            .collect(),
+        _ => return None,
+    };
+    Some(Srgba::new(
+        digits[0],
+        digits[1],
+        digits[2],
+        digits.get(3).copied().unwrap_or(255),
+    ))
+}
+
+/// A number, or a percentage of `percent_scale`.
+fn parse_number(s: &str, percent_scale: f32) -> Option<f32> {
+    let v = match s.strip_suffix('%') {
+        Some(p) => p.parse::<f32>().ok()? / 100.0 * percent_scale,
+        None => s.parse::<f32>().ok()?,
+    };
+    v.is_finite().then_some(v)
+}
+
+/// A hue in degrees, with or without the `deg` unit.
+fn parse_angle(s: &str) -> Option<f32> {
+    let v: f32 = s.strip_suffix("deg").unwrap_or(s).parse().ok()?;
+    v.is_finite().then_some(v)
+}
+
+fn parse_color_function(func: &str, args: &str) -> Option<Srgba<u8>> {
+    let (channels, alpha) = match args.split_once('/') {
+        Some((c, a)) => (c, Some(a.trim())),
+        None => (args, None),
+    };
+    let mut parts: Vec<&str> = channels
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|p| !p.is_empty())
+        .collect();
+    // Legacy `rgba(r, g, b, a)` form
+    let alpha = match (alpha, parts.len()) {
+        (None, 4) => parts.pop(),
+        (a, _) => a,
+    };
+    if parts.len() != 3 {
+        return None;
+    }
+    let alpha = match alpha {
+        Some(a) => parse_number(a, 1.0)?.clamp(0.0, 1.0),
+        None => 1.0,
+    };
+
+    let rgb: Srgb<f32> = match func {
+        "rgb" | "rgba" => {
+            let c: Vec<f32> = parts
+                .iter()
+                .map(|p| parse_number(p, 255.0).map(|v| v / 255.0))
+                .collect::<Option<_>>()?;
+            Srgb::new(c[0], c[1], c[2])
+        }
+        "hsl" | "hsla" => {
+            let h = parse_angle(parts[0])?;
+            let sat = parse_number(parts[1], 100.0)? / 100.0;
+            let light = parse_number(parts[2], 100.0)? / 100.0;
+            Srgb::from_color(Hsl::new(h, sat.clamp(0.0, 1.0), light.clamp(0.0, 1.0)))
+        }
+        "oklch" => {
+            let l = parse_number(parts[0], 1.0)?;
+            // 100% chroma is 0.4 in CSS Color 4
+            let c = parse_number(parts[1], 0.4)?;
+            let h = parse_angle(parts[2])?;
+            let hex = oklch_to_hex(Oklch::new(l.clamp(0.0, 1.0), c.max(0.0), h));
+            return parse_hex_digits(&hex[1..])
+                .map(|c| Srgba::new(c.red, c.green, c.blue, (alpha * 255.0).round() as u8));
+        }
+        _ => return None,
+    };
+    Some(rgb.clamp().with_alpha(alpha).into_format())
 }
 
 pub fn hsl_to_hex(h: f32, s: f32, l: f32) -> String {
@@ -38,7 +196,9 @@ fn clamp(val: f32, min: f32, max: f32) -> f32 {
     }
 }
 
-fn get_default_builder() -> SchemeBuilder {
+/// Scheme builder settings the automatic palettes start from; `[palette_engine]`
+/// overrides them.
+pub fn default_builder() -> SchemeBuilder {
     SchemeBuilder {
         k_means_count: 255,
         light_theme_threshold: 0.55,
@@ -118,9 +278,121 @@ fn get_default_builder() -> SchemeBuilder {
     }
 }
 
-pub fn generate_palette(base_hex: &str) -> std::collections::HashMap<String, String> {
-    let (r, g, b) = hex_to_rgb_tuple(base_hex);
-    let builder = get_default_builder();
+/// Dark or light scheme for the automatic themes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeVariant {
+    Dark,
+    Light,
+    /// Light for bright images, dark otherwise.
+    Auto,
+}
+
+impl FromStr for ThemeVariant {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "dark" => Ok(ThemeVariant::Dark),
+            "light" => Ok(ThemeVariant::Light),
+            "auto" => Ok(ThemeVariant::Auto),
+            other => Err(anyhow!(
+                "Invalid variant '{}' (expected dark, light or auto)",
+                other
+            )),
+        }
+    }
+}
+
+// Mean Oklab lightness above which `variant = "auto"` picks a light scheme
+const LIGHT_IMAGE_THRESHOLD: f32 = 0.6;
+
+impl ThemeVariant {
+    /// Whether the scheme should be light. `Auto` looks at the image and falls back
+    /// to dark when there is none or it cannot be read.
+    pub fn is_light(&self, image: Option<&str>) -> bool {
+        match self {
+            ThemeVariant::Dark => false,
+            ThemeVariant::Light => true,
+            ThemeVariant::Auto => image
+                .and_then(|path| mean_lightness(path).ok())
+                .is_some_and(|l| l >= LIGHT_IMAGE_THRESHOLD),
+        }
+    }
+}
+
+/// Material 3 dynamic colour variants available to `auto-matugen`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaterialVariant {
+    TonalSpot,
+    Vibrant,
+    Expressive,
+    Fidelity,
+    Content,
+    Neutral,
+    Monochrome,
+    Rainbow,
+    FruitSalad,
+}
+
+impl FromStr for MaterialVariant {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().replace('_', "-").as_str() {
+            "tonal-spot" => Ok(MaterialVariant::TonalSpot),
+            "vibrant" => Ok(MaterialVariant::Vibrant),
+            "expressive" => Ok(MaterialVariant::Expressive),
+            "fidelity" => Ok(MaterialVariant::Fidelity),
+            "content" => Ok(MaterialVariant::Content),
+            "neutral" => Ok(MaterialVariant::Neutral),
+            "monochrome" => Ok(MaterialVariant::Monochrome),
+            "rainbow" => Ok(MaterialVariant::Rainbow),
+            "fruit-salad" => Ok(MaterialVariant::FruitSalad),
+            other => Err(anyhow!(
+                "Invalid material variant '{}' (expected tonal-spot, vibrant, expressive, fidelity, content, neutral, monochrome, rainbow or fruit-salad)",
+                other
+            )),
+        }
+    }
+}
+
+impl From<MaterialVariant> for material_colors::dynamic_color::Variant {
+    fn from(v: MaterialVariant) -> Self {
+        use material_colors::dynamic_color::Variant;
+        match v {
+            MaterialVariant::TonalSpot => Variant::TonalSpot,
+            MaterialVariant::Vibrant => Variant::Vibrant,
+            MaterialVariant::Expressive => Variant::Expressive,
+            MaterialVariant::Fidelity => Variant::Fidelity,
+            MaterialVariant::Content => Variant::Content,
+            MaterialVariant::Neutral => Variant::Neutral,
+            MaterialVariant::Monochrome => Variant::Monochrome,
+            MaterialVariant::Rainbow => Variant::Rainbow,
+            MaterialVariant::FruitSalad => Variant::FruitSalad,
+        }
+    }
+}
+
+/// Mirror the Oklch lightness of a colour, keeping its hue.
+fn invert_lightness(hex: &str) -> Result<String> {
+    let c = parse_rgb(hex)?.into_format::<f32>();
+    let mut lch = Oklch::from_color(c);
+    lch.l = (1.0 - lch.l).clamp(0.05, 0.98);
+    Ok(oklch_to_hex(lch))
+}
+
+/// Palette derived from a single colour with `builder`. `light` forces a light or
+/// dark scheme; `None` lets the scheme builder decide from the colour's lightness.
+pub fn generate_palette(
+    base_hex: &str,
+    light: Option<bool>,
+    mut builder: SchemeBuilder,
+) -> Result<std::collections::HashMap<String, String>> {
+    let (r, g, b) = hex_to_rgb_tuple(base_hex)?;
+    if let Some(light) = light {
+        // Out of the 0–1 lightness range, so every image lands on the same side
+        builder.light_theme_threshold = if light { -1.0 } else { 2.0 };
+    }
 
     // Crear una imagen 1x1 para generar desde un solo color usando el API de imagen
     let pixels = [Srgb::new(r, g, b)];
@@ -129,21 +401,19 @@ pub fn generate_palette(base_hex: &str) -> std::collections::HashMap<String, Str
     match builder.generate_from_image(image_ref) {
         Ok(scheme) => {
             let mut palette = std::collections::HashMap::new();
-            let to_hex = |c: Srgb<u8>| format!("#{:02x}{:02x}{:02x}", c.red, c.green, c.blue);
-
-            palette.insert("bg".to_string(), to_hex(scheme.base));
-            palette.insert("surface".to_string(), to_hex(scheme.base_high));
-            palette.insert("muted".to_string(), to_hex(scheme.muted));
-            palette.insert("text".to_string(), to_hex(scheme.text));
-            palette.insert("primary".to_string(), to_hex(scheme.fg_accents[0]));
-            palette.insert("highlight".to_string(), to_hex(scheme.fg_accents[1]));
-            palette.insert("accent".to_string(), to_hex(scheme.fg_accents[2]));
-            palette.insert("secondary".to_string(), to_hex(scheme.fg_accents[3]));
-            palette
+            palette.insert("bg".to_string(), to_hex(scheme.base.into()));
+            palette.insert("surface".to_string(), to_hex(scheme.base_high.into()));
+            palette.insert("muted".to_string(), to_hex(scheme.muted.into()));
+            palette.insert("text".to_string(), to_hex(scheme.text.into()));
+            palette.insert("primary".to_string(), to_hex(scheme.fg_accents[0].into()));
+            palette.insert("highlight".to_string(), to_hex(scheme.fg_accents[1].into()));
+            palette.insert("accent".to_string(), to_hex(scheme.fg_accents[2].into()));
+            palette.insert("secondary".to_string(), to_hex(scheme.fg_accents[3].into()));
+            Ok(palette)
         }
         Err(_) => {
             // Fallback al sistema HSL original
-            let (h, s, l) = hex_to_hsl(base_hex);
+            let (h, s, l) = hex_to_hsl(base_hex)?;
             let mut palette = std::collections::HashMap::new();
             palette.insert(
                 "bg".to_string(),
@@ -186,30 +456,337 @@ pub fn generate_palette(base_hex: &str) -> std::collections::HashMap<String, Str
                     clamp(l * 0.7, 0.0, 1.0),
                 ),
             );
-            palette
+            if light == Some(true) {
+                for key in ["bg", "surface", "muted", "text"] {
+                    let inverted = invert_lightness(&palette[key])?;
+                    palette.insert(key.to_string(), inverted);
+                }
+            }
+            Ok(palette)
+        }
+    }
+}
+
+/// Classic colour-wheel harmonies, computed in Oklch so hue steps look even.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Harmony {
+    Complementary,
+    Analogous,
+    Triadic,
+    /// Split complementary: the two neighbours of the complement.
+    Split,
+    /// Square: four hues 90° apart.
+    Tetradic,
+    Monochrome,
+}
+
+impl FromStr for Harmony {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "complementary" => Ok(Harmony::Complementary),
+            "analogous" => Ok(Harmony::Analogous),
+            "triadic" => Ok(Harmony::Triadic),
+            "split" | "split-complementary" => Ok(Harmony::Split),
+            "tetradic" | "square" => Ok(Harmony::Tetradic),
+            "monochrome" | "monochromatic" => Ok(Harmony::Monochrome),
+            other => Err(anyhow!(
+                "Invalid harmony '{}' (expected complementary, analogous, triadic, split, tetradic or monochrome)",
+                other
+            )),
+        }
+    }
+}
+
+impl Harmony {
+    /// `(hue offset, lightness shift, chroma factor)` for primary, secondary, accent
+    /// and highlight, relative to the base colour.
+    fn roles(&self) -> [(f32, f32, f32); 4] {
+        match self {
+            Harmony::Complementary => [
+                (0.0, 0.0, 1.0),
+                (0.0, -0.12, 0.8),
+                (180.0, 0.0, 1.0),
+                (180.0, 0.1, 0.8),
+            ],
+            Harmony::Analogous => [
+                (0.0, 0.0, 1.0),
+                (-30.0, 0.0, 1.0),
+                (30.0, 0.0, 1.0),
+                (0.0, 0.12, 0.7),
+            ],
+            Harmony::Triadic => [
+                (0.0, 0.0, 1.0),
+                (120.0, 0.0, 1.0),
+                (240.0, 0.0, 1.0),
+                (0.0, 0.12, 0.7),
+            ],
+            Harmony::Split => [
+                (0.0, 0.0, 1.0),
+                (150.0, 0.0, 1.0),
+                (210.0, 0.0, 1.0),
+                (0.0, 0.12, 0.7),
+            ],
+            Harmony::Tetradic => [
+                (0.0, 0.0, 1.0),
+                (90.0, 0.0, 1.0),
+                (180.0, 0.0, 1.0),
+                (270.0, 0.0, 1.0),
+            ],
+            Harmony::Monochrome => [
+                (0.0, 0.0, 1.0),
+                (0.0, -0.15, 0.6),
+                (0.0, 0.12, 0.8),
+                (0.0, 0.2, 0.5),
+            ],
         }
     }
 }
 
+/// Hex of an Oklch colour, reducing chroma until it fits in sRGB.
+fn oklch_to_hex(mut color: Oklch) -> String {
+    loop {
+        let srgb = Srgb::from_color(color);
+        let in_gamut = [srgb.red, srgb.green, srgb.blue]
+            .iter()
+            .all(|v| (-1e-4..=1.0 + 1e-4).contains(v));
+        if in_gamut || color.chroma < 1e-3 {
+            return to_hex(srgb.clamp().into_format::<u8>().into());
+        }
+        color.chroma *= 0.95;
+    }
+}
+
+/// Dark theme built from a colour-wheel harmony of `base_hex`. The neutrals are
+/// tinted with the base hue and the four colour roles follow `harmony`, with their
+/// lightness kept in the range that reads on a dark background.
+pub fn harmony_palette(
+    base_hex: &str,
+    harmony: Harmony,
+) -> Result<std::collections::HashMap<String, String>> {
+    let (r, g, b) = hex_to_rgb_tuple(base_hex)?;
+    let base = Oklch::from_color(Srgb::new(r, g, b).into_format::<f32>());
+    let hue = base.hue.into_degrees();
+    let chroma = base.chroma;
+    let lightness = base.l.clamp(0.6, 0.8);
+    let neutral = |l: f32, c: f32| oklch_to_hex(Oklch::new(l, (chroma * c).min(0.04), hue));
+
+    let mut palette = std::collections::HashMap::new();
+    palette.insert("bg".to_string(), neutral(0.18, 0.15));
+    palette.insert("surface".to_string(), neutral(0.24, 0.2));
+    palette.insert("muted".to_string(), neutral(0.72, 0.2));
+    palette.insert("text".to_string(), neutral(0.95, 0.05));
+
+    let keys = ["primary", "secondary", "accent", "highlight"];
+    for (key, (offset, dl, dc)) in keys.iter().zip(harmony.roles()) {
+        let color = Oklch::new(
+            (lightness + dl).clamp(0.45, 0.92),
+            chroma * dc,
+            hue + offset,
+        );
+        palette.insert(key.to_string(), oklch_to_hex(color));
+    }
+    Ok(palette)
+}
+
 pub fn extract_from_image(image_path: &str, count: u8) -> Result<Vec<color_thief::Color>> {
-    let img = image::open(Path::new(image_path))?;
+    let img = images::load_image(image_path)?;
     let img = img.to_rgba8();
     let pixels = img.as_raw();
     let palette = color_thief::get_palette(pixels, color_thief::ColorFormat::Rgba, 10, count)?;
     Ok(palette)
 }
 
-pub fn suggest_accent(image_path: &str) -> String {
-    if let Ok(img) = image::open(Path::new(image_path)) {
+/// A colour cluster of an image and the share (0.0–1.0) of the weighted pixels in it.
+#[derive(Debug, Clone, Copy)]
+pub struct Swatch {
+    pub color: Srgb<u8>,
+    pub share: f32,
+}
+
+impl Swatch {
+    pub fn hex(&self) -> String {
+        to_hex(self.color.into())
+    }
+}
+
+/// How `extract_weighted` counts pixels.
+#[derive(Debug, Clone)]
+pub struct ExtractOptions {
+    /// Image areas covered by text, as fractions `[x0, y0, x1, y1]`.
+    pub text_areas: Vec<[f32; 4]>,
+    /// Weight of the pixels under text; 1.0 counts them like the rest.
+    pub text_weight: f32,
+    /// Leave out near-black and near-white pixels, unless nothing else is left.
+    pub ignore_extremes: bool,
+}
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        ExtractOptions {
+            text_areas: Vec::new(),
+            text_weight: 0.25,
+            ignore_extremes: true,
+        }
+    }
+}
+
+// Oklab lightness outside which a pixel counts as near-black / near-white
+const EXTREME_DARK: f32 = 0.12;
+const EXTREME_LIGHT: f32 = 0.95;
+const KMEANS_ITERATIONS: usize = 24;
+
+fn oklab_distance2(a: Oklab, b: Oklab) -> f32 {
+    (a.l - b.l).powi(2) + (a.a - b.a).powi(2) + (a.b - b.b).powi(2)
+}
+
+/// Cluster the image in Oklab with weighted k-means and return up to `count`
+/// swatches sorted by share. Pixels are weighted by alpha and by `options`.
+pub fn extract_weighted(
+    image_path: &str,
+    count: u8,
+    options: &ExtractOptions,
+) -> Result<Vec<Swatch>> {
+    use rand::rngs::StdRng;
+    use rand::{RngExt, SeedableRng};
+
+    let img = images::load_image(image_path)?
+        .thumbnail(128, 128)
+        .to_rgba8();
+    let (w, h) = img.dimensions();
+    let under_text = |x: u32, y: u32| {
+        let (u, v) = ((x as f32 + 0.5) / w as f32, (y as f32 + 0.5) / h as f32);
+        options
+            .text_areas
+            .iter()
+            .any(|[x0, y0, x1, y1]| (*x0..*x1).contains(&u) && (*y0..*y1).contains(&v))
+    };
+
+    let mut points: Vec<(Oklab, f32)> = Vec::new();
+    let mut extremes: Vec<(Oklab, f32)> = Vec::new();
+    for (x, y, p) in img.enumerate_pixels() {
+        let alpha = p[3] as f32 / 255.0;
+        if alpha < 0.05 {
+            continue;
+        }
+        let lab = Oklab::from_color(Srgb::new(p[0], p[1], p[2]).into_format::<f32>());
+        let weight = if under_text(x, y) {
+            alpha * options.text_weight
+        } else {
+            alpha
+        };
+        if options.ignore_extremes && !(EXTREME_DARK..=EXTREME_LIGHT).contains(&lab.l) {
+            extremes.push((lab, weight));
+        } else {
+            points.push((lab, weight));
+        }
+    }
+    // Mostly black or white images keep them rather than return nothing
+    let total_weight = |pts: &[(Oklab, f32)]| pts.iter().map(|p| p.1).sum::<f32>();
+    if total_weight(&points) < total_weight(&extremes) * 0.05 {
+        points.append(&mut extremes);
+    }
+    let total = total_weight(&points);
+    if points.is_empty() || total <= 0.0 {
+        return Err(anyhow!("No usable pixels in {}", image_path));
+    }
+
+    // k-means++ seeding with a fixed seed, so the same image gives the same palette
+    let k = (count.max(1) as usize).min(points.len());
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut centers: Vec<Oklab> = vec![points[0].0];
+    while centers.len() < k {
+        let d: Vec<f32> = points
+            .iter()
+            .map(|(lab, wt)| {
+                wt * centers
+                    .iter()
+                    .map(|c| oklab_distance2(*lab, *c))
+                    .fold(f32::MAX, f32::min)
+            })
+            .collect();
+        let sum: f32 = d.iter().sum();
+        if sum <= 0.0 {
+            break;
+        }
+        let mut target = rng.random::<f32>() * sum;
+        let pick = d
+            .iter()
+            .position(|v| {
+                target -= v;
+                target <= 0.0
+            })
+            .unwrap_or(points.len() - 1);
+        centers.push(points[pick].0);
+    }
+
+    let mut weights = vec![0.0f32; centers.len()];
+    for _ in 0..KMEANS_ITERATIONS {
+        let mut sums = vec![(0.0f32, 0.0f32, 0.0f32); centers.len()];
+        weights.iter_mut().for_each(|w| *w = 0.0);
+        for (lab, wt) in &points {
+            let nearest = (0..centers.len())
+                .min_by(|&a, &b| {
+                    oklab_distance2(*lab, centers[a]).total_cmp(&oklab_distance2(*lab, centers[b]))
+                })
+                .unwrap_or(0);
+            sums[nearest].0 += lab.l * wt;
+            sums[nearest].1 += lab.a * wt;
+            sums[nearest].2 += lab.b * wt;
+            weights[nearest] += wt;
+        }
+        let mut moved = 0.0f32;
+        for (i, center) in centers.iter_mut().enumerate() {
+            if weights[i] > 0.0 {
+                let (l, a, b) = sums[i];
+                let next = Oklab::new(l / weights[i], a / weights[i], b / weights[i]);
+                moved = moved.max(oklab_distance2(*center, next));
+                *center = next;
+            }
+        }
+        if moved < 1e-8 {
+            break;
+        }
+    }
+
+    let mut swatches: Vec<Swatch> = centers
+        .iter()
+        .zip(&weights)
+        .filter(|(_, wt)| **wt > 0.0)
+        .map(|(c, wt)| Swatch {
+            color: Srgb::from_color(*c).clamp().into_format(),
+            share: wt / total,
+        })
+        .collect();
+    swatches.sort_by(|a, b| b.share.total_cmp(&a.share));
+    Ok(swatches)
+}
+
+/// Mean Oklab lightness (0.0–1.0) of the opaque pixels of an image.
+pub fn mean_lightness(image_path: &str) -> Result<f32> {
+    let img = images::load_image(image_path)?
+        .thumbnail(256, 256)
+        .to_rgba8();
+    let (sum, count) =
+        img.pixels()
+            .filter(|p| p[3] >= 128)
+            .fold((0.0f32, 0u32), |(sum, count), p| {
+                let c = Srgb::new(p[0], p[1], p[2]).into_format::<f32>();
+                (sum + Oklab::from_color(c).l, count + 1)
+            });
+    Ok(sum / count.max(1) as f32)
+}
+
+pub fn suggest_accent(image_path: &str, builder: &SchemeBuilder) -> String {
+    if let Ok(img) = images::load_image(image_path) {
         let img = img.to_rgb8();
         let (width, height) = img.dimensions();
         let pixels: Vec<Srgb<u8>> = img.pixels().map(|p| Srgb::new(p[0], p[1], p[2])).collect();
         let image_ref = ImageRef::new(width, height, &pixels).unwrap();
 
-        let builder = get_default_builder();
         if let Ok(scheme) = builder.generate_from_image(image_ref) {
-            let ac = scheme.fg_accents[0];
-            return format!("#{:02x}{:02x}{:02x}", ac.red, ac.green, ac.blue);
+            return to_hex(scheme.fg_accents[0].into());
         }
     }
 
@@ -224,9 +801,11 @@ pub fn suggest_accent(image_path: &str) -> String {
 
     let mut scored: Vec<(f32, String)> = Vec::new();
     for c in &colors {
-        let hex = format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b);
-        let (_, s, l) = hex_to_hsl(&hex);
-        if l < 0.08 || l > 0.92 || s < 0.05 {
+        let hex = to_hex(Srgb::new(c.r, c.g, c.b).into());
+        let Ok((_, s, l)) = hex_to_hsl(&hex) else {
+            continue;
+        };
+        if !(0.08..=0.92).contains(&l) || s < 0.05 {
             continue;
         }
         let lum_score = 1.0 - (l - 0.4).abs() * 2.0;
@@ -236,7 +815,7 @@ pub fn suggest_accent(image_path: &str) -> String {
 
     if scored.is_empty() {
         let c = &colors[0];
-        return format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b);
+        return to_hex(Srgb::new(c.r, c.g, c.b).into());
     }
 
     scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
@@ -1,20 +1,79 @@
-use anyhow::{Context, Result};
-use serde::Deserialize;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Deserializer};
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
+use crate::rotation::{Moment, Rotation};
+use crate::{palette_engine, themes};
+
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub defaults: HashMap<String, toml::Value>,
+    #[serde(default, deserialize_with = "theme_tables")]
     pub themes: Option<HashMap<String, HashMap<String, String>>>,
+    /// Overrides for the scheme builder behind `auto` themes.
+    pub palette_engine: Option<toml::Table>,
+    /// Reusable brand profiles, picked with `brand_kit`.
+    pub brands: Option<HashMap<String, BrandKit>>,
     #[serde(rename = "post")]
     pub posts: Option<Vec<PostConfig>>,
 }
 
+/// `[brands.<name>]`: what a brand sets on every post that uses it.
+/// Post values win over the kit, and the kit over `[defaults]`.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct BrandKit {
+    pub brand: Option<String>,
+    pub url: Option<String>,
+    pub logo: Option<String>,
+    pub overlay: Option<String>,
+    pub theme: Option<String>,
+    pub accent: Option<String>,
+    pub font_heading: Option<String>,
+    pub font_body: Option<String>,
+    pub font_mono: Option<String>,
+}
+
+/// Layouts a carousel slide can use.
+pub const SLIDE_LAYOUTS: [&str; 4] = ["cover", "content", "quote", "image"];
+
+/// One carousel slide. Everything but `text` falls back to the post.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub struct Slide {
+    #[serde(default)]
+    pub text: String,
+    pub theme: Option<String>,
+    pub accent: Option<String>,
+    /// Background of this slide instead of its part of the post's image.
+    pub image: Option<String>,
+    /// One of `SLIDE_LAYOUTS`; by default the first slide is the cover.
+    pub layout: Option<String>,
+}
+
+impl From<&str> for Slide {
+    fn from(text: &str) -> Self {
+        Slide {
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Slide {
+    /// Whether the slide changes anything besides its text.
+    pub fn is_styled(&self) -> bool {
+        self.theme.is_some() || self.accent.is_some() || self.image.is_some()
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct PostConfig {
     pub name: String,
+    pub brand_kit: Option<String>,
+    /// Publication date, for date-based theme rotations.
+    #[serde(default, deserialize_with = "date_string")]
+    pub date: Option<String>,
     pub title: Option<String>,
     pub quote: Option<String>,
     pub image: Option<String>,
@@ -29,8 +88,22 @@ pub struct PostConfig {
     pub author: Option<String>,
     pub tag: Option<String>,
     pub ppi: Option<u32>,
-    pub slides: Option<Vec<String>>,
+    /// Carousel slides: plain text or `{ text, theme, accent, image, layout }` tables.
+    #[serde(default, deserialize_with = "slide_entries")]
+    pub slides: Option<Vec<Slide>>,
     pub contour: Option<bool>,
+    pub crop: Option<String>,
+    pub focus: Option<[f32; 2]>,
+    pub effects: Option<Vec<String>>,
+    pub background: Option<String>,
+    /// Background seed; signed so a negative value is reported for this post.
+    pub seed: Option<i64>,
+    pub stylize: Option<String>,
+    pub contrast: Option<String>,
+    pub legibility: Option<String>,
+    pub variant: Option<String>,
+    pub material_variant: Option<String>,
+    pub palette_engine: Option<String>,
     pub recolor: Option<bool>,
     pub recolor_intensity: Option<f32>,
     pub source: Option<String>,
@@ -42,7 +115,353 @@ pub struct PostConfig {
 impl Config {
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
         let content = fs::read_to_string(path).context("Failed to read config file")?;
-        let config: Config = toml::from_str(&content).context("Failed to parse config file")?;
+        let mut config: Config = toml::from_str(&content).context("Failed to parse config file")?;
+
+        // Custom theme colours may use any notation `colors::parse_color` accepts;
+        // style roles are only checked
+        for (name, theme) in config.themes.iter_mut().flatten() {
+            for (key, value) in theme.iter_mut().filter(|(k, _)| k.as_str() != "extends") {
+                *value = themes::normalize_value(key, value)
+                    .with_context(|| format!("Theme '{}': invalid '{}'", name, key))?;
+            }
+        }
+        // Unknown [palette_engine] presets or settings fail at load time
+        palette_engine::builder(Some(&config), &[])?;
+        // So do unknown or circular theme `extends`
+        themes::predefined::validate(&config)?;
+        // And brand kits that are not defined
+        let default_kit = config.defaults.get("brand_kit").and_then(|v| v.as_str());
+        let post_kits = config
+            .posts
+            .iter()
+            .flatten()
+            .filter_map(|p| p.brand_kit.as_deref());
+        for kit in default_kit.into_iter().chain(post_kits) {
+            config.brand_kit(kit)?;
+        }
+        // Malformed `rotate:` themes and post dates
+        let default_theme = config.defaults.get("theme").and_then(|v| v.as_str());
+        let other_themes = config
+            .posts
+            .iter()
+            .flatten()
+            .filter_map(|p| p.theme.as_deref())
+            .chain(
+                config
+                    .brands
+                    .iter()
+                    .flatten()
+                    .filter_map(|(_, k)| k.theme.as_deref()),
+            );
+        for theme in default_theme.into_iter().chain(other_themes) {
+            Rotation::parse(theme)?;
+        }
+        let default_date = config.default_date();
+        if let Some(date) = &default_date {
+            date.parse::<Moment>().context("[defaults]")?;
+        }
+        for post in config.posts.iter().flatten() {
+            if let Some(date) = &post.date {
+                date.parse::<Moment>()
+                    .with_context(|| format!("Post '{}'", post.name))?;
+            }
+            // Date cycles need a date, or every build would pick a different theme
+            if post.date.is_none() && default_date.is_none() {
+                let kit_theme = post
+                    .brand_kit
+                    .as_deref()
+                    .or(default_kit)
+                    .and_then(|k| config.brand_kit(k).ok())
+                    .and_then(|k| k.theme.as_deref());
+                let theme = post.theme.as_deref().or(kit_theme).or(default_theme);
+                let slide_themes = post
+                    .slides
+                    .iter()
+                    .flatten()
+                    .filter_map(|s| s.theme.as_deref());
+                for theme in theme.into_iter().chain(slide_themes) {
+                    if Rotation::parse(theme)?.is_some_and(|r| r.by_date()) {
+                        return Err(anyhow!(
+                            "Post '{}': theme '{}' rotates by date; set `date` on the post or in [defaults]",
+                            post.name,
+                            theme
+                        ));
+                    }
+                }
+            }
+            for (i, slide) in post.slides.iter().flatten().enumerate() {
+                if let Some(theme) = &slide.theme {
+                    Rotation::parse(theme)
+                        .with_context(|| format!("Post '{}', slide {}", post.name, i + 1))?;
+                }
+                if let Some(layout) = &slide.layout {
+                    if !SLIDE_LAYOUTS.contains(&layout.as_str()) {
+                        return Err(anyhow!(
+                            "Post '{}', slide {}: invalid layout '{}' (expected {})",
+                            post.name,
+                            i + 1,
+                            layout,
+                            SLIDE_LAYOUTS.join(", ")
+                        ));
+                    }
+                }
+            }
+        }
         Ok(config)
     }
+
+    /// `[defaults] date`, written as a TOML date or a string.
+    pub fn default_date(&self) -> Option<String> {
+        match self.defaults.get("date")? {
+            toml::Value::String(s) => Some(s.clone()),
+            toml::Value::Datetime(d) => Some(d.to_string()),
+            _ => None,
+        }
+    }
+
+    /// The brand kit `post` uses: its own `brand_kit`, else `[defaults] brand_kit`.
+    pub fn post_kit(&self, post: &PostConfig) -> Result<BrandKit> {
+        let name = post
+            .brand_kit
+            .as_deref()
+            .or_else(|| self.defaults.get("brand_kit").and_then(|v| v.as_str()));
+        match name {
+            None | Some("") => Ok(BrandKit::default()),
+            Some(name) => self.brand_kit(name).cloned(),
+        }
+    }
+
+    /// A branded setting: the post's value wins over its kit's, which wins over `[defaults] key`.
+    pub fn branded(
+        &self,
+        post: &Option<String>,
+        kit: &Option<String>,
+        key: &str,
+    ) -> Option<String> {
+        post.clone().or_else(|| kit.clone()).or_else(|| {
+            self.defaults
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+        })
+    }
+
+    /// The brand kit called `name`.
+    pub fn brand_kit(&self, name: &str) -> Result<&BrandKit> {
+        self.brands
+            .as_ref()
+            .and_then(|b| b.get(name))
+            .ok_or_else(|| {
+                let mut known: Vec<&str> = self
+                    .brands
+                    .iter()
+                    .flatten()
+                    .map(|(k, _)| k.as_str())
+                    .collect();
+                known.sort();
+                anyhow!(
+                    "Unknown brand kit '{}' (defined: {})",
+                    name,
+                    if known.is_empty() {
+                        "none".to_string()
+                    } else {
+                        known.join(", ")
+                    }
+                )
+            })
+    }
+}
+
+type ThemeTables = HashMap<String, HashMap<String, String>>;
+
+/// `[themes.*]` tables with every value as a string, so style roles such as
+/// `spacing = 1.25` can be written as plain numbers.
+fn theme_tables<'de, D>(deserializer: D) -> std::result::Result<Option<ThemeTables>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw: Option<HashMap<String, HashMap<String, toml::Value>>> =
+        Option::deserialize(deserializer)?;
+    raw.map(|themes| {
+        themes
+            .into_iter()
+            .map(|(name, theme)| {
+                let theme = theme
+                    .into_iter()
+                    .map(|(key, value)| match value {
+                        toml::Value::String(s) => Ok((key, s)),
+                        toml::Value::Integer(i) => Ok((key, i.to_string())),
+                        toml::Value::Float(f) => Ok((key, f.to_string())),
+                        other => Err(serde::de::Error::custom(format!(
+                            "theme '{}': invalid '{}' = {}",
+                            name, key, other
+                        ))),
+                    })
+                    .collect::<std::result::Result<_, _>>()?;
+                Ok((name, theme))
+            })
+            .collect()
+    })
+    .transpose()
+}
+
+/// A post `date`, written either as a TOML date (`date = 2026-10-19`) or a string.
+fn date_string<'de, D>(deserializer: D) -> std::result::Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw: Option<toml::Value> = Option::deserialize(deserializer)?;
+    raw.map(|value| match value {
+        toml::Value::String(s) => Ok(s),
+        toml::Value::Datetime(d) => Ok(d.to_string()),
+        other => Err(serde::de::Error::custom(format!("invalid date {}", other))),
+    })
+    .transpose()
+}
+
+/// Slides written as strings or as tables.
+fn slide_entries<'de, D>(deserializer: D) -> std::result::Result<Option<Vec<Slide>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Entry {
+        Text(String),
+        Table(Slide),
+    }
+
+    let raw: Option<Vec<Entry>> = Option::deserialize(deserializer)?;
+    Ok(raw.map(|entries| {
+        entries
+            .into_iter()
+            .map(|entry| match entry {
+                Entry::Text(text) => Slide::from(text.as_str()),
+                Entry::Table(slide) => slide,
+            })
+            .collect()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs `toml` through `Config::load`, validation included.
+    fn load(name: &str, toml: &str) -> Result<Config> {
+        let path = std::env::temp_dir().join(format!("rrss-config-{}.toml", name));
+        fs::write(&path, toml).unwrap();
+        let config = Config::load(&path);
+        fs::remove_file(&path).unwrap();
+        config
+    }
+
+    const KITS: &str = r#"
+[defaults]
+brand_kit = "acme"
+brand = "Defaults"
+url = "defaults.example"
+theme = "light"
+
+[brands.acme]
+brand = "Acme"
+url = "acme.example"
+
+[brands.other]
+brand = "Other"
+
+[[post]]
+name = "plain"
+
+[[post]]
+name = "own"
+brand_kit = "other"
+brand = "Own"
+"#;
+
+    #[test]
+    fn post_wins_over_kit_which_wins_over_defaults() {
+        let config = load("kits", KITS).unwrap();
+        let posts = config.posts.as_ref().unwrap();
+        let branded = |value: &Option<String>, from_kit: &Option<String>, key: &str| {
+            config.branded(value, from_kit, key)
+        };
+
+        // The first post takes `[defaults] brand_kit`
+        let (plain, kit) = (&posts[0], config.post_kit(&posts[0]).unwrap());
+        assert_eq!(branded(&plain.brand, &kit.brand, "brand").unwrap(), "Acme");
+        assert_eq!(
+            branded(&plain.url, &kit.url, "url").unwrap(),
+            "acme.example"
+        );
+        assert_eq!(branded(&plain.theme, &kit.theme, "theme").unwrap(), "light");
+
+        // The second picks its own kit, and its own brand beats it
+        let (own, kit) = (&posts[1], config.post_kit(&posts[1]).unwrap());
+        assert_eq!(branded(&own.brand, &kit.brand, "brand").unwrap(), "Own");
+        assert_eq!(
+            branded(&own.url, &kit.url, "url").unwrap(),
+            "defaults.example"
+        );
+    }
+
+    #[test]
+    fn unknown_brand_kits_are_rejected() {
+        let in_post = KITS.replace("brand_kit = \"other\"", "brand_kit = \"missing\"");
+        let err = load("post-kit", &in_post).unwrap_err().to_string();
+        assert_eq!(err, "Unknown brand kit 'missing' (defined: acme, other)");
+
+        let in_defaults = "[defaults]\nbrand_kit = \"missing\"\n";
+        let err = load("default-kit", in_defaults).unwrap_err().to_string();
+        assert_eq!(err, "Unknown brand kit 'missing' (defined: none)");
+    }
+
+    #[test]
+    fn slides_mix_strings_and_tables() {
+        let config = load(
+            "slides",
+            r#"
+[defaults]
+
+[[post]]
+name = "p"
+slides = ["Uno", { text = "Dos", theme = "light", layout = "quote" }, { image = "a.png" }]
+"#,
+        )
+        .unwrap();
+        let slides = config.posts.unwrap()[0].slides.clone().unwrap();
+        assert_eq!(
+            slides,
+            vec![
+                Slide::from("Uno"),
+                Slide {
+                    text: "Dos".to_string(),
+                    theme: Some("light".to_string()),
+                    layout: Some("quote".to_string()),
+                    ..Default::default()
+                },
+                Slide {
+                    image: Some("a.png".to_string()),
+                    ..Default::default()
+                },
+            ]
+        );
+        assert!(!slides[0].is_styled() && slides[1].is_styled() && slides[2].is_styled());
+    }
+
+    #[test]
+    fn unknown_slide_layouts_are_rejected() {
+        let toml = r#"
+[defaults]
+
+[[post]]
+name = "p"
+slides = ["Uno", { text = "Dos", layout = "big" }]
+"#;
+        let err = load("slide-layout", toml).unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "Post 'p', slide 2: invalid layout 'big' (expected cover, content, quote, image)"
+        );
+    }
 }
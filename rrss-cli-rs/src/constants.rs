@@ -1,4 +1,14 @@
 use std::collections::HashMap;
+
+/// Where procedurally generated backgrounds are written (relative to the project root).
+pub const GENERATED_DIR: &str = "assets/generated";
+
+/// Where theme files are loaded from (relative to the project root).
+pub const THEMES_DIR: &str = "themes";
+
+/// Width in pixels SVG logos are rasterized at for colour extraction.
+pub const LOGO_RASTER_SIZE: u32 = 512;
+
 pub fn get_platforms() -> HashMap<&'static str, (u32, u32)> {
     let mut m = HashMap::new();
     m.insert("instagram-post", (1080, 1080));
@@ -0,0 +1,456 @@
+use anyhow::{anyhow, Context, Result};
+use palette::{Clamp, FromColor, Oklch, Srgb};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::colors;
+
+/// A foreground/background pairing drawn by the rrss-pkg layouts.
+/// `white` stands for the literal white used on badges and tags.
+pub struct Pairing {
+    pub fg: &'static str,
+    pub bg: &'static str,
+    /// Minimum WCAG 2.x contrast ratio.
+    pub min_ratio: f32,
+    /// Minimum absolute APCA lightness contrast (Lc).
+    pub min_lc: f32,
+    pub usage: &'static str,
+}
+
+/// Every pairing the layouts use, in the order `correct` adjusts them.
+/// WCAG gets the AA text threshold for copy and the large-text / non-text one for
+/// headings, badges and rules. The APCA levels are the ones for large text, since even
+/// captions render above 24px on social media canvases.
+/// The badge comes before the `primary` pairings so those win when both cannot pass.
+pub const PAIRINGS: &[Pairing] = &[
+    Pairing {
+        fg: "text",
+        bg: "bg",
+        min_ratio: 4.5,
+        min_lc: 45.0,
+        usage: "body text",
+    },
+    Pairing {
+        fg: "text",
+        bg: "surface",
+        min_ratio: 4.5,
+        min_lc: 45.0,
+        usage: "header and footer text",
+    },
+    Pairing {
+        fg: "muted",
+        bg: "bg",
+        min_ratio: 4.5,
+        min_lc: 40.0,
+        usage: "captions",
+    },
+    Pairing {
+        fg: "muted",
+        bg: "surface",
+        min_ratio: 4.5,
+        min_lc: 40.0,
+        usage: "captions on cards",
+    },
+    Pairing {
+        fg: "white",
+        bg: "primary",
+        min_ratio: 3.0,
+        min_lc: 30.0,
+        usage: "badges and tags",
+    },
+    Pairing {
+        fg: "primary",
+        bg: "bg",
+        min_ratio: 3.0,
+        min_lc: 30.0,
+        usage: "headings, quote marks and authors",
+    },
+    Pairing {
+        fg: "primary",
+        bg: "surface",
+        min_ratio: 3.0,
+        min_lc: 30.0,
+        usage: "stat cards",
+    },
+    Pairing {
+        fg: "accent",
+        bg: "bg",
+        min_ratio: 3.0,
+        min_lc: 15.0,
+        usage: "accent rules",
+    },
+];
+
+/// What to do when a theme has pairings below their threshold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContrastMode {
+    Off,
+    /// Report failing pairings and keep the theme as is.
+    Warn,
+    /// Nudge lightness until every pairing passes, reporting what changed.
+    Fix,
+    /// Fail the build.
+    Strict,
+}
+
+impl FromStr for ContrastMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "off" | "none" => Ok(ContrastMode::Off),
+            "warn" => Ok(ContrastMode::Warn),
+            "fix" => Ok(ContrastMode::Fix),
+            "strict" => Ok(ContrastMode::Strict),
+            other => Err(anyhow!(
+                "Invalid contrast mode '{}' (expected off, warn, fix or strict)",
+                other
+            )),
+        }
+    }
+}
+
+/// Measured contrast of one pairing.
+pub struct Finding {
+    pub pairing: &'static Pairing,
+    pub ratio: f32,
+    pub lc: f32,
+}
+
+impl Finding {
+    pub fn passes(&self) -> bool {
+        self.ratio >= self.pairing.min_ratio && self.lc.abs() >= self.pairing.min_lc
+    }
+}
+
+impl std::fmt::Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{} ({}): {:.2}:1, Lc {:.0} (needs {}:1, Lc {})",
+            self.pairing.fg,
+            self.pairing.bg,
+            self.pairing.usage,
+            self.ratio,
+            self.lc,
+            self.pairing.min_ratio,
+            self.pairing.min_lc
+        )
+    }
+}
+
+fn parse_hex(key: &str, hex: &str) -> Result<Srgb<f32>> {
+    colors::parse_rgb(hex)
+        .map(|c| c.into_format())
+        .with_context(|| format!("Invalid {} color", key))
+}
+
+/// Look up a pairing role. `None` when the theme does not define it.
+pub fn role_color(theme: &HashMap<String, String>, key: &str) -> Option<Result<Srgb<f32>>> {
+    if key == "white" {
+        return Some(Ok(Srgb::new(1.0, 1.0, 1.0)));
+    }
+    theme.get(key).map(|hex| parse_hex(key, hex))
+}
+
+/// WCAG 2.x relative luminance.
+pub fn relative_luminance(c: Srgb<f32>) -> f32 {
+    let lin = c.into_linear();
+    0.2126 * lin.red + 0.7152 * lin.green + 0.0722 * lin.blue
+}
+
+/// WCAG 2.x contrast ratio, from 1.0 to 21.0.
+pub fn wcag_ratio(fg: Srgb<f32>, bg: Srgb<f32>) -> f32 {
+    let (a, b) = (relative_luminance(fg), relative_luminance(bg));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// APCA (0.0.98G-4g) lightness contrast of text `fg` on `bg`.
+/// Positive for dark text on light backgrounds, negative for light on dark.
+pub fn apca_lc(fg: Srgb<f32>, bg: Srgb<f32>) -> f32 {
+    let screen_y = |c: Srgb<f32>| {
+        let y = 0.2126729 * c.red.powf(2.4)
+            + 0.7151522 * c.green.powf(2.4)
+            + 0.0721750 * c.blue.powf(2.4);
+        // Soft clamp of near-black values
+        if y < 0.022 {
+            y + (0.022 - y).powf(1.414)
+        } else {
+            y
+        }
+    };
+    let (y_fg, y_bg) = (screen_y(fg), screen_y(bg));
+    if (y_bg - y_fg).abs() < 0.0005 {
+        return 0.0;
+    }
+
+    let sapc = if y_bg > y_fg {
+        (y_bg.powf(0.56) - y_fg.powf(0.57)) * 1.14
+    } else {
+        (y_bg.powf(0.65) - y_fg.powf(0.62)) * 1.14
+    };
+    let lc = if sapc.abs() < 0.1 {
+        0.0
+    } else {
+        sapc - 0.027 * sapc.signum()
+    };
+    lc * 100.0
+}
+
+/// The pairing drawn with `fg` on `bg`, if the layouts use it.
+pub fn pairing(fg: &str, bg: &str) -> Option<&'static Pairing> {
+    PAIRINGS.iter().find(|p| p.fg == fg && p.bg == bg)
+}
+
+/// Measure `fg` on `bg` against the thresholds of `pairing`.
+pub fn measure(pairing: &'static Pairing, fg: Srgb<f32>, bg: Srgb<f32>) -> Finding {
+    Finding {
+        pairing,
+        ratio: wcag_ratio(fg, bg),
+        lc: apca_lc(fg, bg),
+    }
+}
+
+/// Measure every layout pairing whose roles the theme defines.
+pub fn audit(theme: &HashMap<String, String>) -> Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+    for pairing in PAIRINGS {
+        if let (Some(fg), Some(bg)) = (role_color(theme, pairing.fg), role_color(theme, pairing.bg))
+        {
+            findings.push(measure(pairing, fg?, bg?));
+        }
+    }
+    Ok(findings)
+}
+
+/// Move `color` to the Oklch lightness `l`, keeping its hue and reducing chroma
+/// only as much as needed to stay inside sRGB.
+fn with_lightness(color: Oklch, l: f32) -> Srgb<f32> {
+    let mut candidate = Oklch::new(l, color.chroma, color.hue);
+    loop {
+        let srgb = Srgb::from_color(candidate);
+        let in_gamut = [srgb.red, srgb.green, srgb.blue]
+            .iter()
+            .all(|v| (-1e-4..=1.0 + 1e-4).contains(v));
+        if in_gamut || candidate.chroma < 1e-3 {
+            // Quantize so the contrast measured here is the one the emitted hex gets
+            let srgb: Srgb<u8> = srgb.clamp().into_format();
+            return srgb.into_format();
+        }
+        candidate.chroma *= 0.9;
+    }
+}
+
+/// Smallest lightness change of `moving` that makes the pairing pass, searching toward
+/// the end of the lightness axis given by `towards` (0.0 or 1.0).
+fn search_lightness(
+    moving: Oklch,
+    measure_with: &dyn Fn(Srgb<f32>) -> Finding,
+    towards: f32,
+) -> Option<Srgb<f32>> {
+    if !measure_with(with_lightness(moving, towards)).passes() {
+        return None;
+    }
+    let (mut near, mut far) = (moving.l, towards);
+    for _ in 0..24 {
+        let mid = (near + far) / 2.0;
+        if measure_with(with_lightness(moving, mid)).passes() {
+            far = mid;
+        } else {
+            near = mid;
+        }
+    }
+    Some(with_lightness(moving, far))
+}
+
+/// Nudge the Oklch lightness of failing roles, keeping their hue, until each pairing
+/// reaches its threshold. The foreground moves unless it is the fixed `white`.
+/// Returns the `(key, old, new)` changes; pairings that cannot reach their target
+/// are pushed as far as the lightness axis allows.
+pub fn correct(theme: &mut HashMap<String, String>) -> Result<Vec<(String, String, String)>> {
+    let mut changes: Vec<&str> = Vec::new();
+    let mut original: HashMap<&str, String> = HashMap::new();
+
+    for pairing in PAIRINGS {
+        let (fg, bg) = match (role_color(theme, pairing.fg), role_color(theme, pairing.bg)) {
+            (Some(fg), Some(bg)) => (fg?, bg?),
+            _ => continue,
+        };
+        if measure(pairing, fg, bg).passes() {
+            continue;
+        }
+
+        let moves_fg = pairing.fg != "white";
+        let (key, moving, fixed) = if moves_fg {
+            (pairing.fg, fg, bg)
+        } else {
+            (pairing.bg, bg, fg)
+        };
+        let measure_with = |candidate: Srgb<f32>| {
+            if moves_fg {
+                measure(pairing, candidate, fixed)
+            } else {
+                measure(pairing, fixed, candidate)
+            }
+        };
+
+        // Move away from the fixed color first; crossing over it is the fallback
+        let lch = Oklch::from_color(moving);
+        let away = if relative_luminance(fixed) > relative_luminance(moving) {
+            0.0
+        } else {
+            1.0
+        };
+        let adjusted = search_lightness(lch, &measure_with, away)
+            .or_else(|| search_lightness(lch, &measure_with, 1.0 - away))
+            .unwrap_or_else(|| with_lightness(lch, away));
+
+        let new = colors::to_hex(adjusted.into_format::<u8>().into());
+        if let Some(old) = theme.insert(key.to_string(), new.clone()) {
+            if old != new && !changes.contains(&key) {
+                changes.push(key);
+            }
+            original.entry(key).or_insert(old);
+        }
+    }
+
+    // One entry per role, from the theme's value to the final one
+    Ok(changes
+        .into_iter()
+        .filter_map(|key| {
+            let old = original.remove(key)?;
+            let new = theme[key].clone();
+            (old != new).then(|| (key.to_string(), old, new))
+        })
+        .collect())
+}
+
+/// Apply `mode` to a resolved theme, printing warnings and corrections to stderr.
+/// `Strict` returns an error listing every failing pairing.
+pub fn enforce(theme: &mut HashMap<String, String>, mode: ContrastMode, name: &str) -> Result<()> {
+    match mode {
+        ContrastMode::Off => return Ok(()),
+        ContrastMode::Fix => {
+            for (key, old, new) in correct(theme)? {
+                eprintln!(
+                    "⚠ Theme '{}': adjusted {} {} → {} for contrast",
+                    name, key, old, new
+                );
+            }
+        }
+        ContrastMode::Warn | ContrastMode::Strict => {}
+    }
+
+    let failing: Vec<String> = audit(theme)?
+        .into_iter()
+        .filter(|f| !f.passes())
+        .map(|f| f.to_string())
+        .collect();
+    if failing.is_empty() {
+        return Ok(());
+    }
+
+    if mode == ContrastMode::Strict {
+        return Err(anyhow!(
+            "Theme '{}' fails contrast checks:\n  {}",
+            name,
+            failing.join("\n  ")
+        ));
+    }
+    for f in failing {
+        eprintln!("⚠ Theme '{}': low contrast {}", name, f);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb(hex: &str) -> Srgb<f32> {
+        parse_hex("test", hex).unwrap()
+    }
+
+    #[test]
+    fn wcag_ratio_matches_known_pairs() {
+        assert!((wcag_ratio(rgb("#000000"), rgb("#ffffff")) - 21.0).abs() < 0.01);
+        assert!((wcag_ratio(rgb("#ffffff"), rgb("#000000")) - 21.0).abs() < 0.01);
+        assert!((wcag_ratio(rgb("#777777"), rgb("#ffffff")) - 4.48).abs() < 0.01);
+        assert!((wcag_ratio(rgb("#336699"), rgb("#336699")) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn apca_lc_matches_reference_values() {
+        // From the APCA 0.0.98G-4g reference implementation
+        for (fg, bg, lc) in [
+            ("#000000", "#ffffff", 106.04),
+            ("#ffffff", "#000000", -107.88),
+            ("#888888", "#ffffff", 63.06),
+            ("#ffffff", "#888888", -68.54),
+            ("#000000", "#aaaaaa", 58.15),
+            ("#aaaaaa", "#000000", -56.24),
+            ("#112233", "#ddeeff", 91.66),
+            ("#ddeeff", "#112233", -93.07),
+        ] {
+            let got = apca_lc(rgb(fg), rgb(bg));
+            assert!(
+                (got - lc).abs() < 0.1,
+                "{} on {}: {} != {}",
+                fg,
+                bg,
+                got,
+                lc
+            );
+        }
+        assert_eq!(apca_lc(rgb("#808080"), rgb("#808080")), 0.0);
+    }
+
+    #[test]
+    fn search_lightness_finds_the_threshold() {
+        let pairing = pairing("text", "bg").unwrap();
+        let bg = rgb("#ffffff");
+        let moving = Oklch::from_color(rgb("#9a9ad0"));
+        let found = search_lightness(moving, &|c| measure(pairing, c, bg), 0.0).unwrap();
+        assert!(measure(pairing, found, bg).passes());
+
+        // Nothing lighter than white can pass on white
+        assert!(search_lightness(moving, &|c| measure(pairing, c, bg), 1.0).is_none());
+    }
+
+    #[test]
+    fn fix_reaches_the_target_keeping_the_hue() {
+        let mut theme: HashMap<String, String> = [
+            ("bg", "#ffffff"),
+            ("surface", "#f4f4f4"),
+            ("text", "#7a8cc8"),
+            ("muted", "#6b7280"),
+            ("primary", "#c0392b"),
+            ("accent", "#16a085"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let before = Oklch::from_color(rgb(&theme["text"]));
+
+        enforce(&mut theme, ContrastMode::Fix, "test").unwrap();
+
+        assert!(audit(&theme).unwrap().iter().all(|f| f.passes()));
+        let after = Oklch::from_color(rgb(&theme["text"]));
+        assert!(after.l < before.l);
+        let hue_shift = (after.hue.into_degrees() - before.hue.into_degrees()).abs();
+        assert!(hue_shift < 3.0, "hue moved by {}", hue_shift);
+    }
+
+    #[test]
+    fn strict_fails_and_off_keeps_the_theme() {
+        let theme: HashMap<String, String> = [("bg", "#ffffff"), ("text", "#eeeeee")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        assert!(enforce(&mut theme.clone(), ContrastMode::Strict, "test").is_err());
+
+        let mut off = theme.clone();
+        enforce(&mut off, ContrastMode::Off, "test").unwrap();
+        assert_eq!(off, theme);
+    }
+}
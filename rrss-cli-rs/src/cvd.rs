@@ -0,0 +1,389 @@
+use anyhow::{anyhow, Context, Result};
+use palette::{LinSrgb, Srgb};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::{colors, images, themes};
+
+/// CIEDE2000 difference below which two theme colours read as the same one.
+pub const INDISTINGUISHABLE: f32 = 6.0;
+
+/// Colour vision deficiencies `rrss proof` simulates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cvd {
+    /// Missing or anomalous L cones (red).
+    Protan,
+    /// Missing or anomalous M cones (green).
+    Deutan,
+    /// Missing or anomalous S cones (blue).
+    Tritan,
+    /// No colour vision at all.
+    Achroma,
+}
+
+pub const ALL: [Cvd; 4] = [Cvd::Protan, Cvd::Deutan, Cvd::Tritan, Cvd::Achroma];
+
+impl FromStr for Cvd {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "protan" | "protanopia" => Ok(Cvd::Protan),
+            "deutan" | "deuteranopia" => Ok(Cvd::Deutan),
+            "tritan" | "tritanopia" => Ok(Cvd::Tritan),
+            "achroma" | "achromatopsia" | "mono" => Ok(Cvd::Achroma),
+            other => Err(anyhow!(
+                "Invalid CVD type '{}' (expected protan, deutan, tritan or achroma)",
+                other
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Cvd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Cvd::Protan => "protan",
+            Cvd::Deutan => "deutan",
+            Cvd::Tritan => "tritan",
+            Cvd::Achroma => "achroma",
+        })
+    }
+}
+
+// Machado, Oliveira & Fernandes (2009) linear RGB matrices for severities 0.1 to 1.0
+// in steps of 0.1; severity 0.0 is the identity.
+const PROTAN: [[[f32; 3]; 3]; 10] = [
+    [
+        [0.856167, 0.182038, -0.038205],
+        [0.029342, 0.955115, 0.015544],
+        [-0.002880, -0.001563, 1.004443],
+    ],
+    [
+        [0.734766, 0.334872, -0.069637],
+        [0.051840, 0.919198, 0.028963],
+        [-0.004928, -0.004209, 1.009137],
+    ],
+    [
+        [0.630323, 0.465641, -0.095964],
+        [0.069181, 0.890046, 0.040773],
+        [-0.006308, -0.007724, 1.014032],
+    ],
+    [
+        [0.539009, 0.579343, -0.118352],
+        [0.082546, 0.866121, 0.051332],
+        [-0.007136, -0.011959, 1.019095],
+    ],
+    [
+        [0.458064, 0.679578, -0.137642],
+        [0.092785, 0.846313, 0.060902],
+        [-0.007494, -0.016807, 1.024301],
+    ],
+    [
+        [0.385450, 0.769005, -0.154455],
+        [0.100526, 0.829802, 0.069673],
+        [-0.007442, -0.022190, 1.029632],
+    ],
+    [
+        [0.319627, 0.849633, -0.169261],
+        [0.106241, 0.815969, 0.077790],
+        [-0.007025, -0.028051, 1.035076],
+    ],
+    [
+        [0.259411, 0.923008, -0.182420],
+        [0.110296, 0.804340, 0.085364],
+        [-0.006276, -0.034346, 1.040622],
+    ],
+    [
+        [0.203876, 0.990338, -0.194214],
+        [0.112975, 0.794542, 0.092483],
+        [-0.005222, -0.041043, 1.046265],
+    ],
+    [
+        [0.152286, 1.052583, -0.204868],
+        [0.114503, 0.786281, 0.099216],
+        [-0.003882, -0.048116, 1.051998],
+    ],
+];
+
+const DEUTAN: [[[f32; 3]; 3]; 10] = [
+    [
+        [0.866435, 0.177704, -0.044139],
+        [0.049567, 0.939063, 0.011370],
+        [-0.003453, 0.007233, 0.996220],
+    ],
+    [
+        [0.760729, 0.319078, -0.079807],
+        [0.090568, 0.889315, 0.020117],
+        [-0.006027, 0.013325, 0.992702],
+    ],
+    [
+        [0.675425, 0.433850, -0.109275],
+        [0.125303, 0.847755, 0.026942],
+        [-0.007950, 0.018572, 0.989378],
+    ],
+    [
+        [0.605511, 0.528560, -0.134071],
+        [0.155318, 0.812366, 0.032316],
+        [-0.009376, 0.023176, 0.986200],
+    ],
+    [
+        [0.547494, 0.607765, -0.155259],
+        [0.181692, 0.781742, 0.036566],
+        [-0.010410, 0.027275, 0.983136],
+    ],
+    [
+        [0.498864, 0.674741, -0.173604],
+        [0.205199, 0.754872, 0.039929],
+        [-0.011131, 0.030969, 0.980162],
+    ],
+    [
+        [0.457771, 0.731899, -0.189670],
+        [0.226409, 0.731012, 0.042579],
+        [-0.011595, 0.034333, 0.977261],
+    ],
+    [
+        [0.422823, 0.781057, -0.203881],
+        [0.245752, 0.709602, 0.044646],
+        [-0.011843, 0.037423, 0.974421],
+    ],
+    [
+        [0.392952, 0.823610, -0.216562],
+        [0.263559, 0.690210, 0.046232],
+        [-0.011910, 0.040281, 0.971630],
+    ],
+    [
+        [0.367322, 0.860646, -0.227968],
+        [0.280085, 0.672501, 0.047413],
+        [-0.011820, 0.042940, 0.968881],
+    ],
+];
+
+const TRITAN: [[[f32; 3]; 3]; 10] = [
+    [
+        [0.926670, 0.092514, -0.019184],
+        [0.021191, 0.964503, 0.014306],
+        [0.008437, 0.054813, 0.936750],
+    ],
+    [
+        [0.895720, 0.133330, -0.029050],
+        [0.029997, 0.945400, 0.024603],
+        [0.013027, 0.104707, 0.882266],
+    ],
+    [
+        [0.905871, 0.127791, -0.033662],
+        [0.026856, 0.941251, 0.031893],
+        [0.013410, 0.148296, 0.838294],
+    ],
+    [
+        [0.948035, 0.089490, -0.037526],
+        [0.014364, 0.946792, 0.038844],
+        [0.010853, 0.193991, 0.795156],
+    ],
+    [
+        [1.017277, 0.027029, -0.044306],
+        [-0.006113, 0.958479, 0.047634],
+        [0.006379, 0.248708, 0.744913],
+    ],
+    [
+        [1.104996, -0.046633, -0.058363],
+        [-0.032137, 0.971635, 0.060503],
+        [0.001336, 0.317922, 0.680742],
+    ],
+    [
+        [1.193214, -0.109812, -0.083402],
+        [-0.058496, 0.979410, 0.079086],
+        [-0.002346, 0.403492, 0.598854],
+    ],
+    [
+        [1.257728, -0.139648, -0.118081],
+        [-0.078003, 0.975409, 0.102594],
+        [-0.003316, 0.501214, 0.502102],
+    ],
+    [
+        [1.278864, -0.125333, -0.153531],
+        [-0.084748, 0.957674, 0.127074],
+        [-0.000989, 0.601151, 0.399838],
+    ],
+    [
+        [1.255528, -0.076749, -0.178779],
+        [-0.078411, 0.930809, 0.147602],
+        [0.004733, 0.691367, 0.303900],
+    ],
+];
+
+const IDENTITY: [[f32; 3]; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+// Achromatopsia keeps only the luminance
+const ACHROMA: [[f32; 3]; 3] = [[0.2126, 0.7152, 0.0722]; 3];
+
+fn lerp(a: &[[f32; 3]; 3], b: &[[f32; 3]; 3], t: f32) -> [[f32; 3]; 3] {
+    let mut out = [[0.0; 3]; 3];
+    for (i, row) in out.iter_mut().enumerate() {
+        for (j, v) in row.iter_mut().enumerate() {
+            *v = a[i][j] + (b[i][j] - a[i][j]) * t;
+        }
+    }
+    out
+}
+
+impl Cvd {
+    /// Linear RGB simulation matrix at `severity` (1.0 is a full dichromacy).
+    /// Dichromacies interpolate between the two nearest of Machado's published
+    /// severities; achromatopsia is blended with the identity.
+    fn matrix(&self, severity: f32) -> [[f32; 3]; 3] {
+        let s = severity.clamp(0.0, 1.0);
+        let table = match self {
+            Cvd::Protan => &PROTAN,
+            Cvd::Deutan => &DEUTAN,
+            Cvd::Tritan => &TRITAN,
+            Cvd::Achroma => return lerp(&IDENTITY, &ACHROMA, s),
+        };
+        let step = s * 10.0;
+        let upper = (step.ceil() as usize).max(1);
+        let lower = if upper == 1 {
+            &IDENTITY
+        } else {
+            &table[upper - 2]
+        };
+        lerp(lower, &table[upper - 1], step - (upper - 1) as f32)
+    }
+}
+
+fn apply(m: &[[f32; 3]; 3], c: LinSrgb) -> LinSrgb {
+    let v = [c.red, c.green, c.blue];
+    let row = |r: [f32; 3]| (r[0] * v[0] + r[1] * v[1] + r[2] * v[2]).clamp(0.0, 1.0);
+    LinSrgb::new(row(m[0]), row(m[1]), row(m[2]))
+}
+
+/// How `color` looks with `cvd` at `severity` (0.0–1.0).
+pub fn simulate(color: Srgb<f32>, cvd: Cvd, severity: f32) -> Srgb<f32> {
+    Srgb::from_linear(apply(&cvd.matrix(severity), color.into_linear()))
+}
+
+/// Simulate `cvd` on every pixel of the image at `input` and save it to `output`.
+pub fn simulate_image(input: &Path, output: &Path, cvd: Cvd, severity: f32) -> Result<()> {
+    let path = input.to_str().context("Invalid image path")?;
+    let mut img = images::load_image(path)?.to_rgba8();
+    let m = cvd.matrix(severity);
+
+    // sRGB decoding of each channel value, computed once
+    let to_linear: Vec<f32> = (0..=255u8)
+        .map(|v| Srgb::new(v, 0, 0).into_format::<f32>().into_linear().red)
+        .collect();
+    for p in img.pixels_mut() {
+        let lin = LinSrgb::new(
+            to_linear[p[0] as usize],
+            to_linear[p[1] as usize],
+            to_linear[p[2] as usize],
+        );
+        let c: Srgb<u8> = Srgb::<f32>::from_linear(apply(&m, lin)).into_format();
+        p[0] = c.red;
+        p[1] = c.green;
+        p[2] = c.blue;
+    }
+    img.save(output)
+        .with_context(|| format!("Failed to write {}", output.display()))
+}
+
+/// Theme keys whose colours are told apart normally but collapse under `cvd`,
+/// as `(key, key, normal ΔE, simulated ΔE)`.
+pub fn collapsed_pairs(
+    theme: &HashMap<String, String>,
+    cvd: Cvd,
+    severity: f32,
+) -> Result<Vec<(&'static str, &'static str, f32, f32)>> {
+    let mut roles = Vec::new();
+    for key in themes::KEYS {
+        if let Some(value) = theme.get(key) {
+            let c = colors::parse_rgb(value)
+                .with_context(|| format!("Invalid {} color", key))?
+                .into_format::<f32>();
+            roles.push((key, c, simulate(c, cvd, severity)));
+        }
+    }
+
+    let mut pairs = Vec::new();
+    for (i, (a, a_color, a_sim)) in roles.iter().enumerate() {
+        for (b, b_color, b_sim) in &roles[i + 1..] {
+            let normal = colors::ciede2000(*a_color, *b_color);
+            let simulated = colors::ciede2000(*a_sim, *b_sim);
+            if normal >= INDISTINGUISHABLE && simulated < INDISTINGUISHABLE {
+                pairs.push((*a, *b, normal, simulated));
+            }
+        }
+    }
+    Ok(pairs)
+}
+
+/// Output path of the simulated copy of `input`.
+pub fn proof_path(input: &Path, output_dir: &Path, cvd: Cvd) -> PathBuf {
+    let stem = input
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "proof".to_string());
+    output_dir.join(format!("{}-{}.png", stem, cvd))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb(hex: &str) -> Srgb<f32> {
+        colors::parse_rgb(hex).unwrap().into_format()
+    }
+
+    #[test]
+    fn grey_stays_grey() {
+        for cvd in ALL {
+            for severity in [0.0, 0.1, 0.35, 0.55, 1.0] {
+                for hex in ["#000000", "#404040", "#808080", "#c8c8c8", "#ffffff"] {
+                    let c = simulate(rgb(hex), cvd, severity);
+                    let g = rgb(hex).red;
+                    for channel in [c.red, c.green, c.blue] {
+                        assert!(
+                            (channel - g).abs() < 0.01,
+                            "{} {} at {}: {:?}",
+                            hex,
+                            cvd,
+                            severity,
+                            c
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn red_green_pair_collapses_under_deuteranopia() {
+        let (red, green) = (rgb("#e06666"), rgb("#93a86a"));
+        assert!(colors::ciede2000(red, green) > 40.0);
+
+        let simulated = colors::ciede2000(
+            simulate(red, Cvd::Deutan, 1.0),
+            simulate(green, Cvd::Deutan, 1.0),
+        );
+        assert!(simulated < INDISTINGUISHABLE, "ΔE {}", simulated);
+
+        let theme: HashMap<String, String> = [("primary", "#e06666"), ("accent", "#93a86a")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let pairs = collapsed_pairs(&theme, Cvd::Deutan, 1.0).unwrap();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].0, pairs[0].1), ("primary", "accent"));
+        assert!(collapsed_pairs(&theme, Cvd::Tritan, 1.0)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn zero_severity_is_the_identity() {
+        for cvd in ALL {
+            assert_eq!(cvd.matrix(0.0), IDENTITY, "{}", cvd);
+        }
+        assert_eq!(Cvd::Deutan.matrix(1.0), DEUTAN[9]);
+    }
+}
@@ -0,0 +1,412 @@
+use anyhow::{anyhow, Context, Result};
+use image::{Rgb, RgbImage};
+use palette::{Clamp, FromColor, Mix, Oklab, Srgb};
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::cli::OutputFormat;
+use crate::{colors, themes};
+
+// Size of one swatch in the PNG export
+const SWATCH_SIZE: u32 = 160;
+
+/// Theme colours in export order: the standard keys first, then any extra ones sorted.
+/// Style roles (gradients, radius, fonts…) are left out.
+fn ordered(palette: &HashMap<String, String>) -> Vec<(&str, &str)> {
+    let mut extra: Vec<&String> = palette
+        .keys()
+        .filter(|k| !themes::KEYS.contains(&k.as_str()) && !themes::is_style_key(k))
+        .collect();
+    extra.sort();
+    themes::KEYS
+        .iter()
+        .copied()
+        .chain(extra.into_iter().map(|k| k.as_str()))
+        .filter_map(|k| palette.get(k).map(|v| (k, v.as_str())))
+        .collect()
+}
+
+/// A palette that serializes as a JSON object in export order rather than sorted.
+pub struct Ordered<'a>(Vec<(&'a str, &'a str)>);
+
+impl<'a> Ordered<'a> {
+    pub fn new(palette: &'a HashMap<String, String>) -> Self {
+        Ordered(ordered(palette))
+    }
+}
+
+impl Serialize for Ordered<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in &self.0 {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+fn parse(key: &str, hex: &str) -> Result<Srgb<u8>> {
+    colors::parse_rgb(hex).with_context(|| format!("Invalid {} color", key))
+}
+
+/// Identifier-safe version of the palette name for CSS, SCSS and JS output.
+fn slug(name: &str) -> String {
+    let s: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    s.trim_matches('-').to_string()
+}
+
+/// Whether `format` produces binary data that should not go to a terminal.
+pub fn is_binary(format: &OutputFormat) -> bool {
+    matches!(format, OutputFormat::Ase | OutputFormat::Png)
+}
+
+/// File extension for `format`.
+pub fn extension(format: &OutputFormat) -> &'static str {
+    match format {
+        OutputFormat::Table => "txt",
+        OutputFormat::Json => "json",
+        OutputFormat::Typst => "typ",
+        OutputFormat::Css => "css",
+        OutputFormat::Scss => "scss",
+        OutputFormat::Tailwind => "js",
+        OutputFormat::Gpl => "gpl",
+        OutputFormat::Ase => "ase",
+        OutputFormat::Base16 => "yaml",
+        OutputFormat::Png => "png",
+    }
+}
+
+/// Render `palette` as `format`. `name` titles the palette inside the file.
+pub fn render(
+    palette: &HashMap<String, String>,
+    name: &str,
+    format: &OutputFormat,
+) -> Result<Vec<u8>> {
+    let colors = ordered(palette);
+    let text = match format {
+        OutputFormat::Table => {
+            let mut out = String::new();
+            for (k, v) in &colors {
+                out.push_str(&format!("{}: {}\n", k, v));
+            }
+            out
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&Ordered(colors))? + "\n",
+        OutputFormat::Typst => {
+            let mut out = format!("  {}: (\n", name);
+            for (k, v) in &colors {
+                out.push_str(&format!("    {}: rgb(\"{}\"),\n", k, v));
+            }
+            out.push_str("  ),\n");
+            out
+        }
+        OutputFormat::Css => {
+            let mut out = String::from(":root {\n");
+            for (k, v) in &colors {
+                out.push_str(&format!("  --{}-{}: {};\n", slug(name), k, v));
+            }
+            out.push_str("}\n");
+            out
+        }
+        OutputFormat::Scss => {
+            let mut out = String::new();
+            for (k, v) in &colors {
+                out.push_str(&format!("${}-{}: {};\n", slug(name), k, v));
+            }
+            out.push_str(&format!("\n${}: (\n", slug(name)));
+            for (k, _) in &colors {
+                out.push_str(&format!("  \"{}\": ${}-{},\n", k, slug(name), k));
+            }
+            out.push_str(");\n");
+            out
+        }
+        OutputFormat::Tailwind => {
+            let mut out = String::from(
+                "/** @type {import('tailwindcss').Config} */\nmodule.exports = {\n  theme: {\n    extend: {\n      colors: {\n",
+            );
+            out.push_str(&format!("        \"{}\": {{\n", slug(name)));
+            for (k, v) in &colors {
+                out.push_str(&format!("          \"{}\": \"{}\",\n", k, v));
+            }
+            out.push_str("        },\n      },\n    },\n  },\n};\n");
+            out
+        }
+        OutputFormat::Gpl => {
+            let mut out = format!(
+                "GIMP Palette\nName: {}\nColumns: {}\n#\n",
+                name,
+                colors.len()
+            );
+            for (k, v) in &colors {
+                let c = parse(k, v)?;
+                out.push_str(&format!("{:3} {:3} {:3}\t{}\n", c.red, c.green, c.blue, k));
+            }
+            out
+        }
+        OutputFormat::Base16 => base16_yaml(palette, name)?,
+        OutputFormat::Ase => return ase(&colors, name),
+        OutputFormat::Png => return swatches(&colors),
+    };
+    Ok(text.into_bytes())
+}
+
+/// Adobe Swatch Exchange: one group named after the palette with an RGB swatch per role.
+fn ase(colors: &[(&str, &str)], name: &str) -> Result<Vec<u8>> {
+    fn utf16_name(out: &mut Vec<u8>, name: &str) {
+        let units: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+        out.extend_from_slice(&(units.len() as u16).to_be_bytes());
+        for u in units {
+            out.extend_from_slice(&u.to_be_bytes());
+        }
+    }
+    fn block(out: &mut Vec<u8>, kind: u16, body: &[u8]) {
+        out.extend_from_slice(&kind.to_be_bytes());
+        out.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        out.extend_from_slice(body);
+    }
+
+    let mut out = Vec::new();
+    out.extend_from_slice(b"ASEF");
+    out.extend_from_slice(&1u16.to_be_bytes());
+    out.extend_from_slice(&0u16.to_be_bytes());
+    out.extend_from_slice(&(colors.len() as u32 + 2).to_be_bytes());
+
+    let mut group = Vec::new();
+    utf16_name(&mut group, name);
+    block(&mut out, 0xc001, &group);
+
+    for (k, v) in colors {
+        let c = parse(k, v)?.into_format::<f32>();
+        let mut body = Vec::new();
+        utf16_name(&mut body, k);
+        body.extend_from_slice(b"RGB ");
+        for channel in [c.red, c.green, c.blue] {
+            body.extend_from_slice(&channel.to_be_bytes());
+        }
+        // Global color
+        body.extend_from_slice(&0u16.to_be_bytes());
+        block(&mut out, 0x0001, &body);
+    }
+
+    block(&mut out, 0xc002, &[]);
+    Ok(out)
+}
+
+/// One square per role, left to right.
+fn swatches(colors: &[(&str, &str)]) -> Result<Vec<u8>> {
+    let mut img = RgbImage::new(SWATCH_SIZE * colors.len().max(1) as u32, SWATCH_SIZE);
+    for (i, (k, v)) in colors.iter().enumerate() {
+        let c = parse(k, v)?;
+        for y in 0..SWATCH_SIZE {
+            for x in 0..SWATCH_SIZE {
+                img.put_pixel(i as u32 * SWATCH_SIZE + x, y, Rgb([c.red, c.green, c.blue]));
+            }
+        }
+    }
+    let mut bytes = Vec::new();
+    img.write_to(
+        &mut std::io::Cursor::new(&mut bytes),
+        image::ImageFormat::Png,
+    )?;
+    Ok(bytes)
+}
+
+/// base16 scheme YAML. The eight roles land on the slots the built-in base16 themes
+/// are read from; the remaining slots are blended from them.
+fn base16_yaml(palette: &HashMap<String, String>, name: &str) -> Result<String> {
+    let get = |key: &str| -> Result<Oklab> {
+        let hex = palette
+            .get(key)
+            .ok_or_else(|| anyhow!("Palette missing '{}'", key))?;
+        Ok(Oklab::from_color(parse(key, hex)?.into_format::<f32>()))
+    };
+    let (bg, surface, muted, text) = (get("bg")?, get("surface")?, get("muted")?, get("text")?);
+    let (primary, highlight, accent, secondary) = (
+        get("primary")?,
+        get("highlight")?,
+        get("accent")?,
+        get("secondary")?,
+    );
+    // Toward white (or black on light themes) for the brightest foreground slots
+    let extreme = Oklab::new(if text.l > bg.l { 1.0 } else { 0.0 }, 0.0, 0.0);
+    let beyond = |t: f32| text.mix(extreme, t);
+
+    let slots = [
+        bg,
+        surface,
+        surface.mix(muted, 0.5),
+        muted,
+        muted.mix(text, 0.5),
+        text,
+        beyond(0.3),
+        beyond(0.6),
+        primary,
+        highlight,
+        highlight.mix(primary, 0.5),
+        secondary.mix(accent, 0.5),
+        accent,
+        accent.mix(secondary, 0.25),
+        secondary,
+        primary.mix(bg, 0.35),
+    ];
+
+    let mut out = format!("scheme: \"{}\"\nauthor: \"rrss-cli-rs\"\n", name);
+    for (i, slot) in slots.iter().enumerate() {
+        let c: Srgb<u8> = Srgb::from_color(*slot).clamp().into_format();
+        out.push_str(&format!(
+            "base{:02X}: \"{:02x}{:02x}{:02x}\"\n",
+            i, c.red, c.green, c.blue
+        ));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn palette(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    /// Two roles out of order, an extra colour and a style role that is left out.
+    fn small() -> HashMap<String, String> {
+        palette(&[
+            ("text", "#f5f5f5"),
+            ("radius", "8pt"),
+            ("brand", "#123456"),
+            ("bg", "#0f0f0f"),
+        ])
+    }
+
+    fn text(format: OutputFormat) -> String {
+        String::from_utf8(render(&small(), "My Brand", &format).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn table() {
+        assert_eq!(
+            text(OutputFormat::Table),
+            "bg: #0f0f0f\ntext: #f5f5f5\nbrand: #123456\n"
+        );
+    }
+
+    #[test]
+    fn json() {
+        assert_eq!(
+            text(OutputFormat::Json),
+            "{\n  \"bg\": \"#0f0f0f\",\n  \"text\": \"#f5f5f5\",\n  \"brand\": \"#123456\"\n}\n"
+        );
+    }
+
+    #[test]
+    fn typst() {
+        assert_eq!(
+            text(OutputFormat::Typst),
+            "  My Brand: (\n    bg: rgb(\"#0f0f0f\"),\n    text: rgb(\"#f5f5f5\"),\n    brand: rgb(\"#123456\"),\n  ),\n"
+        );
+    }
+
+    #[test]
+    fn css() {
+        assert_eq!(
+            text(OutputFormat::Css),
+            ":root {\n  --my-brand-bg: #0f0f0f;\n  --my-brand-text: #f5f5f5;\n  --my-brand-brand: #123456;\n}\n"
+        );
+    }
+
+    #[test]
+    fn scss() {
+        assert_eq!(
+            text(OutputFormat::Scss),
+            "$my-brand-bg: #0f0f0f;\n$my-brand-text: #f5f5f5;\n$my-brand-brand: #123456;\n\n\
+             $my-brand: (\n  \"bg\": $my-brand-bg,\n  \"text\": $my-brand-text,\n  \"brand\": $my-brand-brand,\n);\n"
+        );
+    }
+
+    #[test]
+    fn tailwind() {
+        assert_eq!(
+            text(OutputFormat::Tailwind),
+            "/** @type {import('tailwindcss').Config} */\nmodule.exports = {\n  theme: {\n    extend: {\n      colors: {\n\
+             \x20       \"my-brand\": {\n          \"bg\": \"#0f0f0f\",\n          \"text\": \"#f5f5f5\",\n          \"brand\": \"#123456\",\n\
+             \x20       },\n      },\n    },\n  },\n};\n"
+        );
+    }
+
+    #[test]
+    fn gpl() {
+        assert_eq!(
+            text(OutputFormat::Gpl),
+            "GIMP Palette\nName: My Brand\nColumns: 3\n#\n 15  15  15\tbg\n245 245 245\ttext\n 18  52  86\tbrand\n"
+        );
+    }
+
+    #[test]
+    fn base16() {
+        let full = palette(&[
+            ("bg", "#000000"),
+            ("surface", "#222222"),
+            ("muted", "#888888"),
+            ("text", "#eeeeee"),
+            ("primary", "#ff0000"),
+            ("secondary", "#0000ff"),
+            ("accent", "#00ff00"),
+            ("highlight", "#ffff00"),
+        ]);
+        let out = String::from_utf8(render(&full, "Test", &OutputFormat::Base16).unwrap()).unwrap();
+        assert_eq!(
+            out,
+            "scheme: \"Test\"\nauthor: \"rrss-cli-rs\"\n\
+             base00: \"000000\"\nbase01: \"222222\"\nbase02: \"525252\"\nbase03: \"888888\"\n\
+             base04: \"bababa\"\nbase05: \"eeeeee\"\nbase06: \"f3f3f3\"\nbase07: \"f8f8f8\"\n\
+             base08: \"ff0000\"\nbase09: \"ffff00\"\nbase0A: \"ffa000\"\nbase0B: \"00aabf\"\n\
+             base0C: \"00ff00\"\nbase0D: \"00d68d\"\nbase0E: \"0000ff\"\nbase0F: \"8f0000\"\n"
+        );
+        assert!(render(&small(), "Test", &OutputFormat::Base16).is_err());
+    }
+
+    #[test]
+    fn ase() {
+        let out = render(&small(), "P", &OutputFormat::Ase).unwrap();
+        let mut expected = b"ASEF\x00\x01\x00\x00\x00\x00\x00\x05".to_vec();
+        // Group start named "P"
+        expected.extend_from_slice(b"\xc0\x01\x00\x00\x00\x06\x00\x02\x00P\x00\x00");
+        // bg: name, model, three big-endian floats, global type
+        expected.extend_from_slice(b"\x00\x01\x00\x00\x00\x1a\x00\x03\x00b\x00g\x00\x00RGB ");
+        let bg = Srgb::new(15u8, 15, 15).into_format::<f32>();
+        for channel in [bg.red, bg.green, bg.blue] {
+            expected.extend_from_slice(&channel.to_be_bytes());
+        }
+        expected.extend_from_slice(b"\x00\x00");
+        assert_eq!(&out[..expected.len()], &expected[..]);
+        // Group end closes the file
+        assert_eq!(&out[out.len() - 6..], b"\xc0\x02\x00\x00\x00\x00");
+    }
+
+    #[test]
+    fn png() {
+        let out = render(&small(), "P", &OutputFormat::Png).unwrap();
+        let img = image::load_from_memory(&out).unwrap().to_rgb8();
+        assert_eq!(img.dimensions(), (SWATCH_SIZE * 3, SWATCH_SIZE));
+        assert_eq!(img.get_pixel(0, 0), &Rgb([15, 15, 15]));
+        assert_eq!(img.get_pixel(SWATCH_SIZE, 0), &Rgb([245, 245, 245]));
+        assert_eq!(
+            img.get_pixel(SWATCH_SIZE * 3 - 1, SWATCH_SIZE - 1),
+            &Rgb([18, 52, 86])
+        );
+    }
+}
@@ -1,82 +1,149 @@
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
 use std::collections::HashMap;
 
 use crate::cli::GenerateParams;
+use crate::colors::{MaterialVariant, ThemeVariant};
 use crate::config::Config;
-use crate::{colors, themes};
+use crate::contrast::{self, ContrastMode};
+use crate::legibility::{self, Legibility};
+use crate::{colors, constants, images, palette_engine, rotation, themes};
 
-/// Resolve the theme palette from parameters
+/// Resolve the theme palette from parameters, along with the name of the theme
+/// used (the one picked for this post when the theme is a `rotate:` list)
 fn resolve_theme(
     params: &GenerateParams,
     cfg: Option<&Config>,
-) -> Result<HashMap<String, String>> {
+) -> Result<(String, HashMap<String, String>)> {
+    let name = rotation::theme_for(&params.theme, params.post_index, params.date.as_deref())?;
     let palettes = themes::predefined::get_theme_palettes(cfg);
 
-    if let Some(map) = palettes.get(&params.theme) {
-        return Ok(map.clone());
+    if let Some(map) = palettes.get(&name) {
+        return Ok((name, map.clone()));
     }
 
-    match params.theme.as_str() {
-        "auto" => resolve_auto_theme(params, &palettes),
-        "auto-matugen" => resolve_matugen_theme(params, &palettes),
-        _ => Ok(palettes.get("dark").unwrap().clone()),
-    }
+    // `auto-light`, `auto-matugen-dark`… pin the variant in the theme name
+    let (base, variant) = match name.rsplit_once('-') {
+        Some((base, "light")) if base.starts_with("auto") => (base, Some(ThemeVariant::Light)),
+        Some((base, "dark")) if base.starts_with("auto") => (base, Some(ThemeVariant::Dark)),
+        _ => (name.as_str(), params.variant),
+    };
+    let light = variant.map(|v| v.is_light(params.image.as_deref()));
+
+    let map = match base {
+        "auto" => resolve_auto_theme(params, cfg, &palettes, light)?,
+        "auto-matugen" => resolve_matugen_theme(params, &palettes, light.unwrap_or(false))?,
+        _ => return Err(anyhow!("Unknown theme '{}'", name)),
+    };
+    Ok((name, map))
+}
+
+/// Built-in theme used when an automatic theme has no image to work from.
+fn fallback_theme(
+    palettes: &HashMap<String, HashMap<String, String>>,
+    light: bool,
+) -> HashMap<String, String> {
+    let name = if light { "light" } else { "dark" };
+    palettes
+        .get(name)
+        .or_else(|| palettes.get("dark"))
+        .unwrap()
+        .clone()
 }
 
 fn resolve_auto_theme(
     params: &GenerateParams,
+    cfg: Option<&Config>,
     palettes: &HashMap<String, HashMap<String, String>>,
+    light: Option<bool>,
 ) -> Result<HashMap<String, String>> {
-    if let Some(path) = &params.image {
-        match colors::extract_from_image(path, 8) {
-            Ok(extracted) => {
-                let base = format!(
-                    "#{:02x}{:02x}{:02x}",
-                    extracted[0].r, extracted[0].g, extracted[0].b
-                );
-                Ok(colors::generate_palette(&base))
-            }
-            Err(_) => Ok(palettes.get("dark").unwrap().clone()),
+    let path = match &params.image {
+        Some(p) => p,
+        None => return Ok(fallback_theme(palettes, light.unwrap_or(false))),
+    };
+    let options = colors::ExtractOptions {
+        text_areas: auto_text_areas(params, path),
+        ..Default::default()
+    };
+    // The largest weighted cluster, so text boxes and black or white
+    // margins do not decide the theme
+    match colors::extract_weighted(path, 8, &options) {
+        Ok(swatches) if !swatches.is_empty() => {
+            let builder = palette_engine::builder(cfg, &params.palette_engine)?;
+            colors::generate_palette(&swatches[0].hex(), light, builder)
         }
-    } else {
-        Ok(palettes.get("dark").unwrap().clone())
+        _ => Ok(fallback_theme(palettes, light.unwrap_or(false))),
     }
 }
 
+/// Parts of the image the layout puts text on. Empty when the image
+/// cannot be read, which just leaves every pixel at full weight.
+fn auto_text_areas(params: &GenerateParams, path: &str) -> Vec<[f32; 4]> {
+    let aspect = match images::oriented_dimensions(path) {
+        Ok((width, height)) if width > 0 => height as f32 / width as f32,
+        _ => return Vec::new(),
+    };
+    legibility::text_areas(
+        &params.layout,
+        &params.platform,
+        slide_count(params),
+        aspect,
+    )
+    .unwrap_or_default()
+}
+
 fn resolve_matugen_theme(
     params: &GenerateParams,
     palettes: &HashMap<String, HashMap<String, String>>,
+    light: bool,
 ) -> Result<HashMap<String, String>> {
-    use material_colors::image::{FilterType, ImageReader};
+    use image::imageops::FilterType;
+    use material_colors::image::ImageReader;
     use material_colors::theme::ThemeBuilder;
 
     let path = match &params.image {
         Some(p) => p,
-        None => return Ok(palettes.get("dark").unwrap().clone()),
+        None => return Ok(fallback_theme(palettes, light)),
     };
 
-    let bytes = match std::fs::read(path) {
-        Ok(b) => b,
+    let img = match images::load_image(path) {
+        Ok(i) => i,
         Err(e) => {
             eprintln!("⚠ Could not read image {:?}: {}", path, e);
-            return Ok(palettes.get("dark").unwrap().clone());
+            return Ok(fallback_theme(palettes, light));
         }
     };
 
-    let mut data = match ImageReader::read(bytes) {
+    // Resize to 128x128 for fast & stable color extraction (recommended by material-colors docs).
+    // The oriented, sRGB-converted pixels are re-encoded so material-colors sees the same image.
+    let mut bytes = Vec::new();
+    if let Err(e) = img
+        .resize_exact(128, 128, FilterType::Lanczos3)
+        .write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Png)
+    {
+        eprintln!("⚠ Could not prepare image {:?}: {}", path, e);
+        return Ok(fallback_theme(palettes, light));
+    }
+
+    let data = match ImageReader::read(bytes) {
         Ok(d) => d,
         Err(e) => {
             eprintln!("⚠ Could not decode image {:?}: {}", path, e);
-            return Ok(palettes.get("dark").unwrap().clone());
+            return Ok(fallback_theme(palettes, light));
         }
     };
 
-    // Resize to 128x128 for fast & stable color extraction (recommended by material-colors docs)
-    data.resize(128, 128, FilterType::Lanczos3);
-
     let color = ImageReader::extract_color(&data);
-    let theme = ThemeBuilder::with_source(color).build();
-    let scheme = &theme.schemes.dark;
+    let variant = params
+        .material_variant
+        .unwrap_or(MaterialVariant::TonalSpot);
+    let theme = ThemeBuilder::with_source(color)
+        .variant(variant.into())
+        .build();
+    let scheme = if light {
+        &theme.schemes.light
+    } else {
+        &theme.schemes.dark
+    };
 
     let mut m = HashMap::new();
     m.insert("bg".to_string(), scheme.background.to_hex_with_pound());
@@ -94,50 +161,463 @@ fn resolve_matugen_theme(
     Ok(m)
 }
 
-/// Generate a Typst file from parameters
-pub fn do_generate(params: &GenerateParams, cfg: Option<&Config>) -> Result<String> {
+/// How Typst should address an image path. Generated backgrounds live under the
+/// project root, so they are written root-relative and work wherever the `.typ` is.
+fn typst_path(path: &str) -> String {
+    if path.starts_with(constants::GENERATED_DIR) {
+        format!("/{}", path)
+    } else {
+        path.to_string()
+    }
+}
+
+/// `s` as a Typst string literal, with quotes and backslashes escaped.
+fn typst_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Number of frames the background is spread across: one per slide in a
+/// carousel, a single frame for every other layout.
+fn slide_count(params: &GenerateParams) -> u32 {
+    if params.layout == "carousel" {
+        params.slides.len().max(1) as u32
+    } else {
+        1
+    }
+}
+
+/// Pixel size the background image has to cover.
+/// Carousels stretch a single background across every slide.
+fn background_target(params: &GenerateParams) -> Option<(u32, u32)> {
+    let (w, h) = *constants::get_platforms().get(params.platform.as_str())?;
+    Some((w * slide_count(params), h))
+}
+
+/// Run the Rust-side image pipeline and return the path Typst should load.
+/// Without an image, a procedural background is rendered from the theme if requested.
+/// `scrim` strengthens the overlay with an extra `t.bg` layer of that opacity.
+fn prepare_background(
+    params: &GenerateParams,
+    theme_map: &HashMap<String, String>,
+    scrim: Option<f32>,
+) -> Result<Option<String>> {
+    let path = match (&params.image, params.background) {
+        (Some(p), _) => p.clone(),
+        (None, Some(kind)) => {
+            let size = match background_target(params) {
+                Some(s) => s,
+                None => return Ok(None),
+            };
+            let seed = params
+                .seed
+                .unwrap_or_else(|| images::stable_seed(&params.title));
+            images::generate_background(kind, theme_map, size, seed)?
+        }
+        (None, None) => return Ok(None),
+    };
+
+    let job = images::BackgroundJob {
+        crop: params.crop.zip(background_target(params)),
+        effects: params.effects.clone(),
+        stylize: params
+            .stylize
+            .map(|style| (style, theme_map.clone().into_iter().collect())),
+        scrim: match scrim {
+            Some(alpha) => {
+                let bg = theme_map
+                    .get("bg")
+                    .ok_or_else(|| anyhow::anyhow!("Theme missing 'bg'"))
+                    .and_then(|c| colors::parse_rgb(c))?;
+                Some(([bg.red, bg.green, bg.blue], alpha))
+            }
+            None => None,
+        },
+    };
+    Ok(Some(images::process_background(&path, &job)?))
+}
+
+/// What the text being checked sits on.
+#[derive(Clone, Copy)]
+enum Backdrop<'a> {
+    /// A background drawn for this theme, over the listed carousel frames.
+    Own(Option<&'a [u32]>),
+    /// Frames of the post background, which a slide shares and cannot redraw.
+    Shared(&'a [u32]),
+    /// A slide's own image, under that slide's layout.
+    Slide(&'a str),
+}
+
+/// Check the text regions of the layout against the prepared background and
+/// apply the legibility mode: warn, add a scrim, or switch to the theme's counterpart.
+/// On a shared backdrop the scrim mode only warns, since the image is not this theme's.
+fn ensure_legibility(
+    params: &GenerateParams,
+    cfg: Option<&Config>,
+    theme: &mut String,
+    theme_map: &mut HashMap<String, String>,
+    bg_image: &mut Option<String>,
+    backdrop: Backdrop,
+) -> Result<()> {
+    let mode = params.legibility.unwrap_or(Legibility::Warn);
+    let path = match bg_image {
+        Some(p) if mode != Legibility::Off => p.clone(),
+        _ => return Ok(()),
+    };
+    let (frames, slide_layout) = match backdrop {
+        Backdrop::Own(Some([])) => return Ok(()),
+        Backdrop::Own(frames) => (frames, None),
+        Backdrop::Shared(frames) => (Some(frames), None),
+        Backdrop::Slide(layout) => (None, Some(layout)),
+    };
+    let scene = |image: &str| {
+        legibility::Scene::new(
+            image,
+            params.overlay.as_deref(),
+            &params.layout,
+            slide_layout,
+            &params.platform,
+            slide_count(params),
+            frames,
+        )
+    };
+
+    let current = scene(&path)?;
+    let mut findings = current.check(theme_map, 0.0)?;
+    if findings.iter().all(|f| f.passes()) {
+        return Ok(());
+    }
+
+    match mode {
+        Legibility::Off | Legibility::Warn => {}
+        Legibility::Scrim if matches!(backdrop, Backdrop::Shared(_)) => {}
+        Legibility::Scrim => {
+            let alpha = current.required_scrim(theme_map)?;
+            *bg_image = prepare_background(
+                params,
+                theme_map,
+                Some(alpha.unwrap_or(legibility::MAX_SCRIM)),
+            )?;
+            eprintln!(
+                "⚠ Legibility: strengthened the overlay with a {:.0}% bg layer",
+                alpha.unwrap_or(legibility::MAX_SCRIM) * 100.0
+            );
+            if alpha.is_some() {
+                return Ok(());
+            }
+            findings = scene(bg_image.as_deref().unwrap_or(&path))?.check(theme_map, 0.0)?;
+        }
+        Legibility::Variant => {
+            let (name, mut variant) = variant_theme(params, cfg, theme, theme_map)?;
+            contrast::enforce(&mut variant, ContrastMode::Fix, &name)?;
+
+            // Procedural and stylized backgrounds are drawn from the theme
+            let variant_image = match backdrop {
+                Backdrop::Own(_) | Backdrop::Slide(_) => {
+                    prepare_background(params, &variant, None)?
+                }
+                Backdrop::Shared(_) => Some(path.clone()),
+            };
+            let variant_findings = match &variant_image {
+                Some(image) => scene(image)?.check(&variant, 0.0)?,
+                None => Vec::new(),
+            };
+            if legibility::shortfall(&variant_findings) < legibility::shortfall(&findings) {
+                eprintln!("⚠ Legibility: switched to theme '{}'", name);
+                *theme = name;
+                *theme_map = variant;
+                *bg_image = variant_image;
+                findings = variant_findings;
+            }
+        }
+    }
+
+    for f in findings.iter().filter(|f| !f.passes()) {
+        eprintln!("⚠ Legibility: low contrast over the background {}", f);
+    }
+    Ok(())
+}
+
+/// The light/dark counterpart the variant mode switches to. Only the palette
+/// changes: the style roles, fonts and any accent the post set are carried over.
+fn variant_theme(
+    params: &GenerateParams,
+    cfg: Option<&Config>,
+    theme: &str,
+    theme_map: &HashMap<String, String>,
+) -> Result<(String, HashMap<String, String>)> {
+    let palettes = themes::predefined::get_theme_palettes(cfg);
+    let (name, mut variant) = legibility::counterpart(theme, theme_map, &palettes)?;
+    // The gradient is drawn in the palette's own colours, so it goes with it
+    variant.retain(|key, _| !themes::is_style_key(key) || key == "bg-gradient");
+    for (key, value) in variant.iter_mut() {
+        *value = themes::normalize_value(key, value)
+            .with_context(|| format!("Theme '{}': invalid '{}'", name, key))?;
+    }
+    for (key, value) in theme_map {
+        if themes::is_style_key(key) && key != "bg-gradient" {
+            variant.insert(key.clone(), value.clone());
+        }
+    }
+    let accent_set = (params.auto_accent && params.image.is_some())
+        || (params.accent != "theme" && params.accent != "auto");
+    if let (true, Some(accent)) = (accent_set, theme_map.get("accent")) {
+        variant.insert("accent".to_string(), accent.clone());
+    }
+    Ok((name, variant))
+}
+
+/// Resolve the theme for `params` with its fonts and accent applied and its
+/// contrast checked, along with the name of the theme used.
+fn build_theme(
+    params: &GenerateParams,
+    cfg: Option<&Config>,
+) -> Result<(String, HashMap<String, String>)> {
     let mut final_accent = params.accent.clone();
 
     // Auto accent handling
     if params.auto_accent {
         if let Some(img_path) = &params.image {
-            final_accent = colors::suggest_accent(img_path);
+            let builder = palette_engine::builder(cfg, &params.palette_engine)?;
+            final_accent = colors::suggest_accent(img_path, &builder);
         }
     }
 
     // Theme resolution
-    let theme_map = resolve_theme(params, cfg)?;
+    let (theme, mut theme_map) = resolve_theme(params, cfg)?;
+    // Every colour reaches Typst as hex, whatever notation the theme was written in
+    for (key, value) in theme_map.iter_mut() {
+        *value = themes::normalize_value(key, value)
+            .with_context(|| format!("Theme '{}': invalid '{}'", theme, key))?;
+    }
+    // Fonts given for the post override the theme's
+    for (key, font) in [
+        ("font-heading", &params.font_heading),
+        ("font-body", &params.font_body),
+        ("font-mono", &params.font_mono),
+    ] {
+        if let Some(f) = font {
+            theme_map.insert(key.to_string(), f.clone());
+        }
+    }
+    if final_accent != "theme" && final_accent != "auto" {
+        let accent = colors::normalize_color(&final_accent).context("Invalid accent")?;
+        theme_map.insert("accent".to_string(), accent);
+    }
 
-    // Start building the Typst file
-    let mut out = String::new();
-    out.push_str("// Auto-generado por rrss-cli-rs\n");
-    out.push_str("#import \"@local/rrss:0.1.0\": *\n\n");
+    // Contrast: generated palettes are corrected; curated themes are taken as
+    // designed (`themes show` audits them) unless a mode is asked for
+    let contrast = params.contrast.unwrap_or(if theme.starts_with("auto") {
+        ContrastMode::Fix
+    } else {
+        ContrastMode::Off
+    });
+    contrast::enforce(&mut theme_map, contrast, &theme)?;
+    Ok((theme, theme_map))
+}
 
-    // Construct Typst dictionary for theme
-    out.push_str("#let t = (");
+/// Typst dictionary literal for a theme.
+fn theme_dict(theme_map: &HashMap<String, String>) -> Result<String> {
+    let mut out = String::from("(");
     let mut keys: Vec<_> = theme_map.keys().collect();
     keys.sort();
     for k in keys {
         let v = theme_map.get(k).unwrap();
-        if k.as_str() == "accent" && final_accent != "theme" {
-            out.push_str(&format!("{}: rgb(\"{}\"), ", k, final_accent));
+        if themes::is_style_key(k) {
+            // Gradients, radius, weights, spacing and fonts
+            out.push_str(&format!("\"{}\": {}, ", k, themes::style_typst(k, v)?));
         } else {
             out.push_str(&format!("{}: rgb(\"{}\"), ", k, v));
         }
     }
+    out.push(')');
+    Ok(out)
+}
+
+/// A styled slide: the post with the slide's theme, accent and image, and the
+/// theme that gives before any legibility change.
+struct SlidePlan {
+    params: GenerateParams,
+    theme: String,
+    theme_map: HashMap<String, String>,
+    /// The slide's theme differs from the post's own.
+    own_theme: bool,
+    /// The slide has a background image of its own.
+    own_image: bool,
+}
 
-    // Inject fonts if provided
-    if let Some(f) = &params.font_heading {
-        out.push_str(&format!(" \"font-heading\": \"{}\", ", f));
+/// Themes of the styled slides, compared against the post theme `base` as built,
+/// before the legibility check. Plain slides get `None`.
+fn slide_plans(
+    params: &GenerateParams,
+    cfg: Option<&Config>,
+    base: &HashMap<String, String>,
+) -> Result<Vec<Option<SlidePlan>>> {
+    let mut plans = Vec::new();
+    for (i, slide) in params.slides.iter().enumerate() {
+        if !slide.is_styled() {
+            plans.push(None);
+            continue;
+        }
+        let slide_params = GenerateParams {
+            theme: slide.theme.clone().unwrap_or_else(|| params.theme.clone()),
+            accent: slide
+                .accent
+                .clone()
+                .unwrap_or_else(|| params.accent.clone()),
+            auto_accent: match &slide.accent {
+                Some(accent) => accent == "auto",
+                None => params.auto_accent,
+            },
+            image: slide.image.clone().or_else(|| params.image.clone()),
+            slides: Vec::new(),
+            ..params.clone()
+        };
+        let (theme, theme_map) =
+            build_theme(&slide_params, cfg).with_context(|| format!("Slide {}", i + 1))?;
+        plans.push(Some(SlidePlan {
+            own_theme: &theme_map != base,
+            own_image: slide.image.is_some(),
+            params: slide_params,
+            theme,
+            theme_map,
+        }));
     }
-    if let Some(f) = &params.font_body {
-        out.push_str(&format!(" \"font-body\": \"{}\", ", f));
+    Ok(plans)
+}
+
+/// Carousel frames the post theme shows over the post background: every frame
+/// but those of slides with their own theme or image.
+fn post_frames(params: &GenerateParams, plans: &[Option<SlidePlan>]) -> Vec<u32> {
+    (0..slide_count(params))
+        .filter(|&i| match plans.get(i as usize) {
+            Some(Some(plan)) if params.layout == "carousel" => !plan.own_theme && !plan.own_image,
+            _ => true,
+        })
+        .collect()
+}
+
+/// Layout the carousel draws slide `i` with: its own, else the cover for a titled
+/// first slide and `content` for the rest.
+fn slide_layout(params: &GenerateParams, i: usize) -> &str {
+    match params.slides[i].layout.as_deref() {
+        Some(layout) => layout,
+        None if i == 0 && !params.title.is_empty() => "cover",
+        None => "content",
     }
-    if let Some(f) = &params.font_mono {
-        out.push_str(&format!(" \"font-mono\": \"{}\", ", f));
+}
+
+/// Carousel slides as Typst values, plus the `#let` lines of the slide themes.
+/// Plain slides stay strings; the others become dictionaries the carousel reads
+/// `t`, `bg-image` and `layout` from. Each styled slide is checked for legibility
+/// over its own image, or over its frame of the post background `bg_image`.
+fn slide_entries(
+    params: &GenerateParams,
+    cfg: Option<&Config>,
+    plans: &[Option<SlidePlan>],
+    (theme, theme_map): (&str, &HashMap<String, String>),
+    bg_image: Option<&str>,
+) -> Result<(Vec<String>, String)> {
+    let mut entries = Vec::new();
+    let mut lets = String::new();
+    for (i, (slide, plan)) in params.slides.iter().zip(plans).enumerate() {
+        let text = typst_string(slide.text.trim());
+        if plan.is_none() && slide.layout.is_none() {
+            entries.push(text);
+            continue;
+        }
+
+        let mut fields = vec![format!("body: {}", text)];
+        if let Some(plan) = plan {
+            // Slides that keep the post theme start from its legible version
+            let (mut slide_theme, mut slide_map) = match plan.own_theme {
+                true => (plan.theme.clone(), plan.theme_map.clone()),
+                false => (theme.to_string(), theme_map.clone()),
+            };
+            let context = || format!("Slide {}", i + 1);
+            if plan.own_image {
+                let mut img = prepare_background(&plan.params, &slide_map, None)?;
+                ensure_legibility(
+                    &plan.params,
+                    cfg,
+                    &mut slide_theme,
+                    &mut slide_map,
+                    &mut img,
+                    Backdrop::Slide(slide_layout(params, i)),
+                )
+                .with_context(context)?;
+                if let Some(img) = img {
+                    fields.push(format!(
+                        "bg-image: image(\"{}\", width: 100%, height: 100%, fit: \"cover\")",
+                        typst_path(&img)
+                    ));
+                }
+            } else if plan.own_theme {
+                // The slide's accent and mode over the frames of the post background
+                let shared_params = GenerateParams {
+                    slides: params.slides.clone(),
+                    ..plan.params.clone()
+                };
+                ensure_legibility(
+                    &shared_params,
+                    cfg,
+                    &mut slide_theme,
+                    &mut slide_map,
+                    &mut bg_image.map(String::from),
+                    Backdrop::Shared(&[i as u32]),
+                )
+                .with_context(context)?;
+            }
+            if &slide_map != theme_map {
+                let name = format!("t-slide-{}", i + 1);
+                lets.push_str(&format!("#let {} = {}\n", name, theme_dict(&slide_map)?));
+                fields.push(format!("t: {}", name));
+            }
+        }
+        if let Some(layout) = &slide.layout {
+            fields.push(format!("layout: \"{}\"", layout));
+        }
+        entries.push(format!("({})", fields.join(", ")));
     }
+    Ok((entries, lets))
+}
+
+pub fn do_generate(params: &GenerateParams, cfg: Option<&Config>) -> Result<String> {
+    let (mut theme, base_map) = build_theme(params, cfg)?;
+    let mut theme_map = base_map.clone();
+
+    // Background pre-processing (procedural fill, crop, effects)
+    let mut bg_image = prepare_background(params, &theme_map, None)?;
+
+    // Text over the background image, on the frames the post theme shows
+    let plans = slide_plans(params, cfg, &base_map)?;
+    let frames = post_frames(params, &plans);
+    ensure_legibility(
+        params,
+        cfg,
+        &mut theme,
+        &mut theme_map,
+        &mut bg_image,
+        Backdrop::Own(Some(&frames)),
+    )?;
+
+    // Per-slide themes and backgrounds
+    let (slides, slide_themes) = slide_entries(
+        params,
+        cfg,
+        &plans,
+        (&theme, &theme_map),
+        bg_image.as_deref(),
+    )?;
+
+    // Start building the Typst file
+    let mut out = String::new();
+    out.push_str("// Auto-generado por rrss-cli-rs\n");
+    out.push_str("#import \"@local/rrss:0.1.0\": *\n\n");
 
-    out.push_str(")\n\n");
+    // Construct Typst dictionary for theme
+    out.push_str(&format!("#let t = {}\n", theme_dict(&theme_map)?));
+    out.push_str(&slide_themes);
+    out.push('\n');
 
     // Configuration line (explicit dimensions)
     out.push_str(&format!(
@@ -150,7 +630,7 @@ pub fn do_generate(params: &GenerateParams, cfg: Option<&Config>) -> Result<Stri
     out.push_str("    t,\n");
 
     // Required parameters
-    out.push_str(&format!("    brand: \"{}\",\n", params.brand));
+    out.push_str(&format!("    brand: {},\n", typst_string(&params.brand)));
 
     // Logo
     if let Some(l) = &params.logo {
@@ -166,23 +646,29 @@ pub fn do_generate(params: &GenerateParams, cfg: Option<&Config>) -> Result<Stri
 
     // Title & Quote
     if !params.title.is_empty() {
-        out.push_str(&format!("    title: \"{}\",\n", params.title));
+        out.push_str(&format!("    title: {},\n", typst_string(&params.title)));
     }
     if !params.quote.is_empty() {
-        out.push_str(&format!("    quote-text: \"{}\",\n", params.quote));
+        out.push_str(&format!(
+            "    quote-text: {},\n",
+            typst_string(&params.quote)
+        ));
     }
 
     // Optional metadata
     if !params.author.is_empty() {
-        out.push_str(&format!("    author: \"{}\",\n", params.author));
+        out.push_str(&format!("    author: {},\n", typst_string(&params.author)));
     }
     if let Some(s) = &params.source {
-        out.push_str(&format!("    source: \"{}\",\n", s));
+        out.push_str(&format!("    source: {},\n", typst_string(s)));
     }
 
     // Background and overlay
-    if let Some(img) = &params.image {
-        out.push_str(&format!("    bg-image: image(\"{}\", width: 100%),\n", img));
+    if let Some(img) = &bg_image {
+        out.push_str(&format!(
+            "    bg-image: image(\"{}\", width: 100%),\n",
+            typst_path(img)
+        ));
     }
     if let Some(ov) = &params.overlay {
         if ov.ends_with(".svg") {
@@ -200,7 +686,7 @@ pub fn do_generate(params: &GenerateParams, cfg: Option<&Config>) -> Result<Stri
 
     // Colors and URL
     if !params.url.is_empty() {
-        out.push_str(&format!("    url: \"{}\",\n", params.url));
+        out.push_str(&format!("    url: {},\n", typst_string(&params.url)));
     }
 
     // Contour and tags
@@ -208,20 +694,141 @@ pub fn do_generate(params: &GenerateParams, cfg: Option<&Config>) -> Result<Stri
         out.push_str("    contour: true,\n");
     }
     if let Some(t) = &params.tag {
-        out.push_str(&format!("    tag: \"{}\",\n", t));
+        out.push_str(&format!("    tag: {},\n", typst_string(t)));
     }
 
     // Slides
-    if let Some(s) = &params.slides {
-        if s.contains('|') {
-            let list: Vec<String> = s.split('|').map(|x| format!("\"{}\"", x.trim())).collect();
-            out.push_str(&format!("    slides: ({},),\n", list.join(", ")));
-        } else {
-            out.push_str(&format!("    slides: {},\n", s));
+    if !slides.is_empty() {
+        out.push_str("    slides: (\n");
+        for slide in &slides {
+            out.push_str(&format!("        {},\n", slide));
         }
+        out.push_str("    ),\n");
     }
 
     out.push_str(")\n");
 
     Ok(out)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Slide;
+
+    /// A dark carousel without images, so no legibility check runs.
+    fn carousel(slides: Vec<Slide>) -> GenerateParams {
+        GenerateParams {
+            brand: String::new(),
+            title: "T".to_string(),
+            quote: String::new(),
+            image: None,
+            logo: None,
+            overlay: None,
+            accent: "theme".to_string(),
+            auto_accent: false,
+            url: String::new(),
+            platform: "instagram-post".to_string(),
+            layout: "carousel".to_string(),
+            theme: "dark".to_string(),
+            post_index: 0,
+            date: None,
+            author: String::new(),
+            source: None,
+            tag: None,
+            slides,
+            contour: false,
+            crop: None,
+            effects: Vec::new(),
+            background: None,
+            seed: None,
+            stylize: None,
+            contrast: None,
+            legibility: None,
+            variant: None,
+            material_variant: None,
+            palette_engine: Vec::new(),
+            font_heading: None,
+            font_body: None,
+            font_mono: None,
+        }
+    }
+
+    fn slides() -> Vec<Slide> {
+        vec![
+            Slide::from("Uno"),
+            Slide {
+                text: "Dos".to_string(),
+                layout: Some("quote".to_string()),
+                ..Default::default()
+            },
+            Slide {
+                text: "Tres".to_string(),
+                theme: Some("light".to_string()),
+                ..Default::default()
+            },
+            Slide {
+                text: "Cuatro".to_string(),
+                theme: Some("dark".to_string()),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn only_styled_slides_get_a_plan() {
+        let params = carousel(slides());
+        let (_, base) = build_theme(&params, None).unwrap();
+        let plans = slide_plans(&params, None, &base).unwrap();
+
+        assert!(plans[0].is_none() && plans[1].is_none());
+        let light = plans[2].as_ref().unwrap();
+        assert_eq!(light.theme, "light");
+        assert!(light.own_theme && !light.own_image);
+        // Naming the post theme again changes nothing
+        assert!(!plans[3].as_ref().unwrap().own_theme);
+    }
+
+    #[test]
+    fn slides_become_strings_or_dictionaries() {
+        let params = carousel(slides());
+        let (theme, theme_map) = build_theme(&params, None).unwrap();
+        let plans = slide_plans(&params, None, &theme_map).unwrap();
+        let (entries, lets) =
+            slide_entries(&params, None, &plans, (&theme, &theme_map), None).unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                r#""Uno""#,
+                r#"(body: "Dos", layout: "quote")"#,
+                r#"(body: "Tres", t: t-slide-3)"#,
+                r#"(body: "Cuatro")"#,
+            ]
+        );
+        assert!(lets.starts_with("#let t-slide-3 = ("));
+        assert_eq!(lets.lines().count(), 1);
+    }
+
+    #[test]
+    fn slide_layouts_default_to_cover_then_content() {
+        let params = carousel(slides());
+        let layouts: Vec<_> = (0..4).map(|i| slide_layout(&params, i)).collect();
+        assert_eq!(layouts, ["cover", "quote", "content", "content"]);
+
+        let untitled = GenerateParams {
+            title: String::new(),
+            ..params
+        };
+        assert_eq!(slide_layout(&untitled, 0), "content");
+    }
+
+    #[test]
+    fn typst_strings_round_trip_quotes_and_backslashes() {
+        let text = r#"Say "hi" \ C:\path\"#;
+        let literal = typst_string(text);
+        assert_eq!(literal, r#""Say \"hi\" \\ C:\\path\\""#);
+        // Typst reads `\"` and `\\` like JSON does
+        assert_eq!(serde_json::from_str::<String>(&literal).unwrap(), text);
+    }
+}
@@ -1,22 +1,59 @@
-use image::{GenericImageView, ImageBuffer, Rgba};
-use palette::{Srgb, Mix};
-use std::collections::HashMap;
-use std::str::FromStr;
+use anyhow::{anyhow, Context, Result};
+use image::metadata::Orientation;
+use image::{DynamicImage, GenericImageView, ImageBuffer, ImageDecoder, ImageReader, Rgba};
+use moxcms::{ColorProfile, DataColorSpace, Layout, TransformOptions};
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
+use palette::{Clamp, FromColor, LinSrgb, Mix, Oklab, Srgb};
+use rand::rngs::StdRng;
+use rand::{RngExt, SeedableRng};
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
-use anyhow::{Context, Result, anyhow};
+use std::str::FromStr;
+
+use crate::{colors, themes};
 
 // Security limits
 const MAX_IMAGE_SIZE_MB: u64 = 256;
+// Longest side kept after loading; larger photos are downscaled, not rejected
 const MAX_IMAGE_DIMENSION: u32 = 4096;
+// Hard cap checked before decoding, so a small compressed file cannot expand
+// into a huge allocation
+const MAX_DECODE_DIMENSION: u32 = 16384;
+const MAX_DECODE_ALLOC_MB: u64 = 1024;
 
-pub fn recolor_image(
-    image_path: &str,
-    theme_name: &str,
-    theme: &HashMap<String, String>,
-    output_path: Option<&str>,
-    intensity: f32,
-) -> Result<String> {
-    // Resource limit check
+// Longest side of the thumbnail used to score crop windows
+const CROP_ANALYSIS_SIZE: u32 = 256;
+
+/// How a background image is cropped to the platform aspect ratio.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CropMode {
+    /// Keep the normalized point `(x, y)` (0.0–1.0) as close to the center as possible.
+    Focus(f32, f32),
+    /// Trim the edges with the least visual information (entropy crop).
+    Auto,
+}
+
+impl FromStr for CropMode {
+    type Err = anyhow::Error;
+
+    /// Accepts `auto`, `center` or a focal point written as `x,y`.
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "auto" | "entropy" => Ok(CropMode::Auto),
+            "center" => Ok(CropMode::Focus(0.5, 0.5)),
+            other => {
+                let (x, y) = other
+                    .split_once(',')
+                    .ok_or_else(|| anyhow!("Invalid crop mode '{}'", other))?;
+                let x: f32 = x.trim().parse().context("Invalid focus x")?;
+                let y: f32 = y.trim().parse().context("Invalid focus y")?;
+                Ok(CropMode::Focus(x, y))
+            }
+        }
+    }
+}
+
+fn check_file_size(image_path: &str) -> Result<()> {
     let metadata = std::fs::metadata(image_path).context("Failed to read image metadata")?;
     if metadata.len() > MAX_IMAGE_SIZE_MB * 1024 * 1024 {
         return Err(anyhow!(
@@ -25,40 +62,183 @@ pub fn recolor_image(
             MAX_IMAGE_SIZE_MB
         ));
     }
-    
-    let img = image::open(image_path).context("Failed to open image")?;
-    let (width, height) = img.dimensions();
-    
-    if width > MAX_IMAGE_DIMENSION || height > MAX_IMAGE_DIMENSION {
+    Ok(())
+}
+
+/// Shared image loader: enforces the file size limit, applies the EXIF orientation,
+/// downscales to `MAX_IMAGE_DIMENSION` and converts embedded ICC profiles to sRGB, so
+/// every analysis sees the pixels Typst shows.
+pub fn load_image(image_path: &str) -> Result<DynamicImage> {
+    check_file_size(image_path)?;
+
+    let mut limits = image::Limits::default();
+    limits.max_alloc = Some(MAX_DECODE_ALLOC_MB * 1024 * 1024);
+
+    let mut reader = ImageReader::open(image_path)
+        .context("Failed to open image")?
+        .with_guessed_format()
+        .context("Failed to detect image format")?;
+    reader.limits(limits);
+    let mut decoder = reader.into_decoder().context("Failed to decode image")?;
+
+    let (width, height) = decoder.dimensions();
+    if width > MAX_DECODE_DIMENSION || height > MAX_DECODE_DIMENSION {
         return Err(anyhow!(
             "Image dimensions too large: {}x{} (max: {}x{})",
-            width, height, MAX_IMAGE_DIMENSION, MAX_IMAGE_DIMENSION
+            width,
+            height,
+            MAX_DECODE_DIMENSION,
+            MAX_DECODE_DIMENSION
         ));
     }
 
+    // Missing or unreadable metadata is not fatal: fall back to the raw pixels
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let icc = decoder.icc_profile().ok().flatten();
+
+    let mut img = DynamicImage::from_decoder(decoder).context("Failed to decode image")?;
+    img.apply_orientation(orientation);
+
+    // Camera photos often exceed the limit; keep the aspect ratio and shrink them
+    let (width, height) = img.dimensions();
+    if width > MAX_IMAGE_DIMENSION || height > MAX_IMAGE_DIMENSION {
+        img = img.resize(
+            MAX_IMAGE_DIMENSION,
+            MAX_IMAGE_DIMENSION,
+            image::imageops::FilterType::Lanczos3,
+        );
+    }
+
+    if let Some(profile) = icc {
+        match convert_to_srgb(&img, &profile) {
+            Ok(converted) => img = converted,
+            Err(e) => eprintln!("⚠ Ignoring ICC profile of {}: {}", image_path, e),
+        }
+    }
+
+    Ok(img)
+}
+
+/// Width and height of the image as displayed, read from the header and the EXIF
+/// orientation without decoding the pixels.
+pub fn oriented_dimensions(image_path: &str) -> Result<(u32, u32)> {
+    let mut decoder = ImageReader::open(image_path)
+        .context("Failed to open image")?
+        .with_guessed_format()
+        .context("Failed to detect image format")?
+        .into_decoder()
+        .context("Failed to decode image")?;
+    let (width, height) = decoder.dimensions();
+    match decoder.orientation().unwrap_or(Orientation::NoTransforms) {
+        Orientation::Rotate90
+        | Orientation::Rotate270
+        | Orientation::Rotate90FlipH
+        | Orientation::Rotate270FlipH => Ok((height, width)),
+        _ => Ok((width, height)),
+    }
+}
+
+/// Convert pixels tagged with an RGB ICC profile to sRGB.
+fn convert_to_srgb(img: &DynamicImage, icc: &[u8]) -> Result<DynamicImage> {
+    let source = ColorProfile::new_from_slice(icc).map_err(|e| anyhow!("{:?}", e))?;
+    if source.color_space != DataColorSpace::Rgb {
+        return Err(anyhow!("unsupported profile colour space"));
+    }
+
+    let srgb = ColorProfile::new_srgb();
+    let transform = source
+        .create_transform_8bit(
+            Layout::Rgba,
+            &srgb,
+            Layout::Rgba,
+            TransformOptions::default(),
+        )
+        .map_err(|e| anyhow!("{:?}", e))?;
+
+    let rgba = img.to_rgba8();
+    let mut out = rgba.clone();
+    transform
+        .transform(rgba.as_raw(), &mut out)
+        .map_err(|e| anyhow!("{:?}", e))?;
+
+    Ok(DynamicImage::ImageRgba8(out))
+}
+
+/// Build `<dir>/<stem>_<suffix>.<ext>` next to the source image.
+fn sibling_path(image_path: &str, suffix: &str) -> Result<PathBuf> {
     let p = Path::new(image_path);
     let root_path = p.parent().unwrap_or_else(|| Path::new("."));
-    let stem = p.file_stem().and_then(|s| s.to_str()).context("Invalid filename")?;
+    let stem = p
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .context("Invalid filename")?;
     let ext = p.extension().and_then(|s| s.to_str()).unwrap_or("png");
-    
-    let default_output = root_path.join(format!("{}_{}.{}", stem, theme_name, ext));
+    Ok(root_path.join(format!("{}_{}.{}", stem, suffix, ext)))
+}
+
+/// Build `constants::GENERATED_DIR/<stem>_<suffix>.<ext>` for a derived file of the
+/// source image, creating the directory if needed.
+fn generated_path(image_path: &str, suffix: &str) -> Result<PathBuf> {
+    let dir = Path::new(crate::constants::GENERATED_DIR);
+    std::fs::create_dir_all(dir).context("Failed to create generated assets directory")?;
+    let name = sibling_path(image_path, suffix)?;
+    Ok(dir.join(name.file_name().context("Invalid filename")?))
+}
+
+fn is_jpeg_path(path: &Path) -> bool {
+    path.extension().is_some_and(|e| {
+        let s = e.to_string_lossy().to_lowercase();
+        s == "jpg" || s == "jpeg"
+    })
+}
+
+/// Save an image, dropping the alpha channel for JPEG outputs.
+fn save_image(img: DynamicImage, out_path: &Path) -> Result<()> {
+    if is_jpeg_path(out_path) {
+        img.to_rgb8()
+            .save(out_path)
+            .context("Failed to save image")?;
+    } else {
+        img.save(out_path).context("Failed to save image")?;
+    }
+    Ok(())
+}
+
+pub fn recolor_image(
+    image_path: &str,
+    theme_name: &str,
+    theme: &HashMap<String, String>,
+    output_path: Option<&str>,
+    intensity: f32,
+) -> Result<String> {
+    let img = load_image(image_path)?;
+    let (width, height) = img.dimensions();
+
+    let default_output = sibling_path(image_path, theme_name)?;
     let out_path = output_path.map(PathBuf::from).unwrap_or(default_output);
-    let is_jpeg = out_path.extension().map_or(false, |e| {
-         let s = e.to_string_lossy().to_lowercase();
-         s == "jpg" || s == "jpeg"
-    });
-
-    let bg_hex = theme.get("bg").ok_or_else(|| anyhow!("Theme missing 'bg'"))?;
-    let primary_hex = theme.get("primary").ok_or_else(|| anyhow!("Theme missing 'primary'"))?;
-    
-    let bg_color: palette::LinSrgb = Srgb::from_str(bg_hex).map_err(|_| anyhow!("Invalid bg color"))?.into_linear();
-    let primary_color: palette::LinSrgb = Srgb::from_str(primary_hex).map_err(|_| anyhow!("Invalid primary color"))?.into_linear();
-    
+    let is_jpeg = is_jpeg_path(&out_path);
+
+    let bg_hex = theme
+        .get("bg")
+        .ok_or_else(|| anyhow!("Theme missing 'bg'"))?;
+    let primary_hex = theme
+        .get("primary")
+        .ok_or_else(|| anyhow!("Theme missing 'primary'"))?;
+
+    let bg_color: palette::LinSrgb = colors::parse_rgb(bg_hex)
+        .context("Invalid bg color")?
+        .into_format::<f32>()
+        .into_linear();
+    let primary_color: palette::LinSrgb = colors::parse_rgb(primary_hex)
+        .context("Invalid primary color")?
+        .into_format::<f32>()
+        .into_linear();
+
     let mut buffer = ImageBuffer::new(width, height);
-    
+
     for (x, y, pixel) in img.pixels() {
         let (r, g, b, a) = (pixel[0], pixel[1], pixel[2], pixel[3]);
-        
+
         // Skip fully transparent pixels if not JPEG
         if a == 0 && !is_jpeg {
             buffer.put_pixel(x, y, Rgba([0, 0, 0, 0]));
@@ -66,58 +246,1104 @@ pub fn recolor_image(
         }
 
         // Convert to linear sRGB for correct mixing
-        let original = Srgb::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0).into_linear();
-        
+        let original =
+            Srgb::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0).into_linear();
+
         // Grayscale value (luminance)
         // Rec. 709 luminance coefficients
         let gray = original.red * 0.2126 + original.green * 0.7152 + original.blue * 0.0722;
-        
+
         // Map gray to duotone gradient (mix bg and primary based on luminance)
         // gray 0.0 -> bg, gray 1.0 -> primary
         let duotone = bg_color.mix(primary_color, gray);
-        
+
         // Blend with original based on intensity
         let blended = original.mix(duotone, intensity);
-        
+
         let final_srgb: Srgb = Srgb::from_linear(blended);
-        
-        buffer.put_pixel(x, y, Rgba([
-             (final_srgb.red * 255.0).round() as u8,
-             (final_srgb.green * 255.0).round() as u8,
-             (final_srgb.blue * 255.0).round() as u8,
-             a
-        ]));
-    }
-    
-    if is_jpeg {
-        // Convert to RGB8 (drop alpha)
-        let dynamic = image::DynamicImage::ImageRgba8(buffer);
-        let rgb_img = dynamic.to_rgb8();
-        rgb_img.save(&out_path).context("Failed to save image")?;
+
+        buffer.put_pixel(
+            x,
+            y,
+            Rgba([
+                (final_srgb.red * 255.0).round() as u8,
+                (final_srgb.green * 255.0).round() as u8,
+                (final_srgb.blue * 255.0).round() as u8,
+                a,
+            ]),
+        );
+    }
+
+    save_image(DynamicImage::ImageRgba8(buffer), &out_path)?;
+
+    Ok(out_path.to_string_lossy().to_string())
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// A post-processing step applied to the background before Typst places it.
+/// Every effect operates on linear-light RGB.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+    /// Gaussian blur; standard deviation (sigma) in pixels at a 1080 px short side.
+    Blur(f32),
+    /// Darken the corners (0.0–1.0).
+    Vignette(f32),
+    /// Monochrome film grain (0.0–1.0).
+    Grain(f32),
+    /// Exposure multiplier (1.0 = unchanged).
+    Brightness(f32),
+    /// Contrast around middle grey (1.0 = unchanged).
+    Contrast(f32),
+    /// Mix towards luminance (0.0–1.0).
+    Desaturate(f32),
+    /// Mix towards black (0.0–1.0).
+    Darken(f32),
+}
+
+impl FromStr for Effect {
+    type Err = anyhow::Error;
+
+    /// Accepts `name` or `name:amount`, e.g. `blur:12`, `vignette`, `contrast:1.2`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, amount) = match s.trim().split_once(':') {
+            Some((n, a)) => (
+                n.trim(),
+                Some(
+                    a.trim()
+                        .parse::<f32>()
+                        .with_context(|| format!("Invalid amount in effect '{}'", s))?,
+                ),
+            ),
+            None => (s.trim(), None),
+        };
+        let required = || amount.ok_or_else(|| anyhow!("Effect '{}' needs an amount", name));
+        match name {
+            "blur" => Ok(Effect::Blur(amount.unwrap_or(8.0))),
+            "vignette" => Ok(Effect::Vignette(amount.unwrap_or(0.5))),
+            "grain" => Ok(Effect::Grain(amount.unwrap_or(0.1))),
+            "brightness" => Ok(Effect::Brightness(required()?)),
+            "contrast" => Ok(Effect::Contrast(required()?)),
+            "desaturate" => Ok(Effect::Desaturate(amount.unwrap_or(1.0))),
+            "darken" => Ok(Effect::Darken(amount.unwrap_or(0.3))),
+            other => Err(anyhow!("Unknown effect '{}'", other)),
+        }
+    }
+}
+
+/// Everything the Rust side does to a background before Typst sees it.
+#[derive(Debug, Clone, Default)]
+pub struct BackgroundJob {
+    /// Crop mode plus the pixel size whose aspect ratio has to be matched.
+    pub crop: Option<(CropMode, (u32, u32))>,
+    pub effects: Vec<Effect>,
+    /// Stylization filter plus the resolved theme it draws its inks from.
+    pub stylize: Option<(Stylize, BTreeMap<String, String>)>,
+    /// Uniform layer of the theme's `bg` colour and its opacity, baked into the image
+    /// (so under the overlay) when the legibility check asks for more contrast.
+    pub scrim: Option<([u8; 3], f32)>,
+}
+
+impl BackgroundJob {
+    pub fn is_empty(&self) -> bool {
+        self.crop.is_none()
+            && self.effects.is_empty()
+            && self.stylize.is_none()
+            && self.scrim.is_none()
+    }
+
+    /// Human-readable part of the output file name, e.g. `9x16-auto_fx`.
+    fn tag(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some((mode, target)) = self.crop {
+            let g = gcd(target.0, target.1).max(1);
+            let mode_tag = match mode {
+                CropMode::Auto => "auto".to_string(),
+                CropMode::Focus(fx, fy) => format!(
+                    "f{}-{}",
+                    (fx.clamp(0.0, 1.0) * 100.0).round(),
+                    (fy.clamp(0.0, 1.0) * 100.0).round()
+                ),
+            };
+            parts.push(format!("{}x{}-{}", target.0 / g, target.1 / g, mode_tag));
+        }
+        if !self.effects.is_empty() {
+            parts.push("fx".to_string());
+        }
+        if let Some((style, _)) = &self.stylize {
+            parts.push(style.name().to_string());
+        }
+        if let Some((_, alpha)) = self.scrim {
+            parts.push(format!("scrim{}", (alpha * 100.0).round()));
+        }
+        parts.join("_")
+    }
+}
+
+/// 64-bit FNV-1a; stable across builds so cached files survive recompiles.
+fn fnv1a(bytes: &[u8], mut hash: u64) -> u64 {
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// Bump whenever the output of `process_background` changes for the same job, so
+/// files cached by an older build are not reused.
+const PIPELINE_VERSION: u32 = 2;
+
+/// Cache key for a processed file: pipeline version, source bytes and the job description.
+fn cache_key(source: &[u8], job: &str) -> u64 {
+    let hash = fnv1a(&PIPELINE_VERSION.to_le_bytes(), 0xcbf2_9ce4_8422_2325);
+    fnv1a(job.as_bytes(), fnv1a(source, hash))
+}
+
+/// Crop and post-process a background image.
+///
+/// The result is written to `constants::GENERATED_DIR` as `<stem>_<tag>_<hash>.<ext>`,
+/// where the hash covers the source bytes and the job, so an existing file is reused as-is.
+/// Returns the path Typst should load (the source itself when there is nothing to do).
+pub fn process_background(image_path: &str, job: &BackgroundJob) -> Result<String> {
+    if job.is_empty() {
+        return Ok(image_path.to_string());
+    }
+
+    check_file_size(image_path)?;
+    let source = std::fs::read(image_path).context("Failed to read image")?;
+    let key = cache_key(&source, &format!("{:?}", job));
+    let out_path = generated_path(
+        image_path,
+        &format!("{}_{:012x}", job.tag(), key & 0xffff_ffff_ffff),
+    )?;
+    if out_path.exists() {
+        return Ok(out_path.to_string_lossy().to_string());
+    }
+
+    let mut img = load_image(image_path)?;
+
+    if let Some((mode, target)) = job.crop {
+        let (width, height) = img.dimensions();
+        let (x, y, w, h) = crop_window(&img, target, mode);
+        if w != width || h != height {
+            img = img.crop_imm(x, y, w, h);
+        }
+    }
+
+    if !job.effects.is_empty() {
+        img = apply_effects(&img, &job.effects, key);
+    }
+
+    if let Some((style, theme)) = &job.stylize {
+        img = apply_stylize(&img, *style, theme, key)?;
+    }
+
+    if let Some((color, alpha)) = job.scrim {
+        img = apply_scrim(&img, color, alpha);
+    }
+
+    save_image(img, &out_path)?;
+
+    Ok(out_path.to_string_lossy().to_string())
+}
+
+/// Blend a uniform `color` layer over the image. Unlike the effects this works on
+/// sRGB values, the way Typst composites the overlay the scrim stands in for.
+fn apply_scrim(img: &DynamicImage, color: [u8; 3], alpha: f32) -> DynamicImage {
+    let mut rgba = img.to_rgba8();
+    let alpha = alpha.clamp(0.0, 1.0);
+    for p in rgba.pixels_mut() {
+        for c in 0..3 {
+            let v = p[c] as f32 + (color[c] as f32 - p[c] as f32) * alpha;
+            p[c] = v.round() as u8;
+        }
+    }
+    DynamicImage::ImageRgba8(rgba)
+}
+
+/// Apply `effects` in order, in linear light with premultiplied alpha.
+fn apply_effects(img: &DynamicImage, effects: &[Effect], seed: u64) -> DynamicImage {
+    let rgba = img.to_rgba8();
+    let (width, height) = rgba.dimensions();
+
+    let mut lut = [0.0f32; 256];
+    for (i, v) in lut.iter_mut().enumerate() {
+        *v = Srgb::new(i as u8, i as u8, i as u8)
+            .into_linear::<f32>()
+            .red;
+    }
+
+    let mut px: Vec<[f32; 4]> = rgba
+        .pixels()
+        .map(|p| {
+            let a = p[3] as f32 / 255.0;
+            [
+                lut[p[0] as usize] * a,
+                lut[p[1] as usize] * a,
+                lut[p[2] as usize] * a,
+                a,
+            ]
+        })
+        .collect();
+
+    let short_side = width.min(height) as f32;
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    for effect in effects {
+        match *effect {
+            Effect::Blur(sigma) => {
+                let sigma = sigma * short_side / 1080.0;
+                gaussian_blur(&mut px, width as usize, height as usize, sigma);
+            }
+            Effect::Vignette(strength) => {
+                let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+                for (i, p) in px.iter_mut().enumerate() {
+                    let x = (i % width as usize) as f32 + 0.5;
+                    let y = (i / width as usize) as f32 + 0.5;
+                    let dx = (x - cx) / cx;
+                    let dy = (y - cy) / cy;
+                    let d = ((dx * dx + dy * dy) / 2.0).sqrt();
+                    let t = ((d - 0.35) / 0.65).clamp(0.0, 1.0);
+                    let factor = 1.0 - strength * t * t * (3.0 - 2.0 * t);
+                    for c in &mut p[..3] {
+                        *c *= factor;
+                    }
+                }
+            }
+            Effect::Grain(amount) => {
+                for p in px.iter_mut() {
+                    // Triangular noise in [-1, 1]
+                    let n = rng.random::<f32>() - rng.random::<f32>();
+                    let factor = (1.0 + amount * n).max(0.0);
+                    for c in &mut p[..3] {
+                        *c *= factor;
+                    }
+                }
+            }
+            Effect::Brightness(factor) => {
+                for p in px.iter_mut() {
+                    for c in &mut p[..3] {
+                        *c *= factor;
+                    }
+                }
+            }
+            Effect::Contrast(factor) => {
+                // Power curve pivoting on 18% grey
+                const MID_GREY: f32 = 0.18;
+                for p in px.iter_mut() {
+                    let a = p[3];
+                    if a <= 0.0 {
+                        continue;
+                    }
+                    for c in &mut p[..3] {
+                        *c = MID_GREY * (*c / a / MID_GREY).max(0.0).powf(factor) * a;
+                    }
+                }
+            }
+            Effect::Desaturate(amount) => {
+                for p in px.iter_mut() {
+                    let gray = p[0] * 0.2126 + p[1] * 0.7152 + p[2] * 0.0722;
+                    for c in &mut p[..3] {
+                        *c += (gray - *c) * amount;
+                    }
+                }
+            }
+            Effect::Darken(amount) => {
+                let factor = 1.0 - amount.clamp(0.0, 1.0);
+                for p in px.iter_mut() {
+                    for c in &mut p[..3] {
+                        *c *= factor;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut buffer = ImageBuffer::new(width, height);
+    for (dst, p) in buffer.pixels_mut().zip(px) {
+        let a = p[3].clamp(0.0, 1.0);
+        let unpremultiply = |c: f32| {
+            if a > 0.0 {
+                (c / a).clamp(0.0, 1.0)
+            } else {
+                0.0
+            }
+        };
+        let srgb: Srgb<u8> = Srgb::from_linear(LinSrgb::new(
+            unpremultiply(p[0]),
+            unpremultiply(p[1]),
+            unpremultiply(p[2]),
+        ));
+        *dst = Rgba([srgb.red, srgb.green, srgb.blue, (a * 255.0).round() as u8]);
+    }
+
+    DynamicImage::ImageRgba8(buffer)
+}
+
+/// Approximate a gaussian blur with three successive box blurs per axis.
+fn gaussian_blur(px: &mut [[f32; 4]], width: usize, height: usize, sigma: f32) {
+    if sigma < 0.5 {
+        return;
+    }
+    // Box size giving the same variance as the gaussian over three passes
+    let radius = (((12.0 * sigma * sigma / 3.0 + 1.0).sqrt() - 1.0) / 2.0).round() as usize;
+    if radius == 0 {
+        return;
+    }
+
+    let mut line = Vec::with_capacity(width.max(height));
+    for _ in 0..3 {
+        for y in 0..height {
+            line.clear();
+            line.extend_from_slice(&px[y * width..(y + 1) * width]);
+            box_blur_line(&line, radius, |x, v| px[y * width + x] = v);
+        }
+        for x in 0..width {
+            line.clear();
+            line.extend((0..height).map(|y| px[y * width + x]));
+            box_blur_line(&line, radius, |y, v| px[y * width + x] = v);
+        }
+    }
+}
+
+/// Running-sum box blur over one row or column, clamping at the edges.
+fn box_blur_line(src: &[[f32; 4]], radius: usize, mut write: impl FnMut(usize, [f32; 4])) {
+    let n = src.len();
+    let at = |i: isize| src[i.clamp(0, n as isize - 1) as usize];
+    let window = (2 * radius + 1) as f32;
+
+    let mut sum = [0.0f32; 4];
+    for i in -(radius as isize)..=(radius as isize) {
+        let p = at(i);
+        for c in 0..4 {
+            sum[c] += p[c];
+        }
+    }
+
+    for i in 0..n {
+        write(i, sum.map(|s| s / window));
+        let add = at(i as isize + radius as isize + 1);
+        let sub = at(i as isize - radius as isize);
+        for c in 0..4 {
+            sum[c] += add[c] - sub[c];
+        }
+    }
+}
+
+/// Compute the crop rectangle `(x, y, width, height)` for the given mode.
+fn crop_window(img: &DynamicImage, target: (u32, u32), mode: CropMode) -> (u32, u32, u32, u32) {
+    let (width, height) = img.dimensions();
+    if target.0 == 0 || target.1 == 0 {
+        return (0, 0, width, height);
+    }
+
+    // Largest window with the target ratio that fits inside the image
+    let target_ratio = target.0 as f64 / target.1 as f64;
+    let (crop_w, crop_h) = if (width as f64 / height as f64) > target_ratio {
+        (
+            ((height as f64 * target_ratio).round() as u32).min(width),
+            height,
+        )
+    } else {
+        (
+            width,
+            ((width as f64 / target_ratio).round() as u32).min(height),
+        )
+    };
+
+    let (x, y) = match mode {
+        CropMode::Focus(fx, fy) => {
+            let place = |focus: f32, size: u32, window: u32| -> u32 {
+                let center = focus.clamp(0.0, 1.0) as f64 * size as f64;
+                (center - window as f64 / 2.0).clamp(0.0, (size - window) as f64) as u32
+            };
+            (place(fx, width, crop_w), place(fy, height, crop_h))
+        }
+        CropMode::Auto => entropy_offset(img, crop_w, crop_h),
+    };
+
+    (x, y, crop_w, crop_h)
+}
+
+/// Shannon entropy of the luma histogram inside a rectangle of `luma`.
+fn region_entropy(luma: &image::GrayImage, x0: u32, y0: u32, x1: u32, y1: u32) -> f64 {
+    let mut hist = [0u32; 256];
+    let mut total = 0u32;
+    for y in y0..y1 {
+        for x in x0..x1 {
+            hist[luma.get_pixel(x, y)[0] as usize] += 1;
+            total += 1;
+        }
+    }
+    if total == 0 {
+        return 0.0;
+    }
+    hist.iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Find the window offset by repeatedly trimming the lower-entropy edge slice,
+/// working on a downscaled copy for speed.
+fn entropy_offset(img: &DynamicImage, crop_w: u32, crop_h: u32) -> (u32, u32) {
+    let (width, height) = img.dimensions();
+    let thumb = img
+        .thumbnail(CROP_ANALYSIS_SIZE, CROP_ANALYSIS_SIZE)
+        .to_luma8();
+    let (tw, th) = thumb.dimensions();
+    let scale_x = tw as f64 / width as f64;
+    let scale_y = th as f64 / height as f64;
+
+    // Only one axis has excess; the other spans the full image
+    let horizontal = crop_w < width;
+    let (size, window, scale) = if horizontal {
+        (
+            tw,
+            ((crop_w as f64 * scale_x).round() as u32).min(tw),
+            scale_x,
+        )
+    } else {
+        (
+            th,
+            ((crop_h as f64 * scale_y).round() as u32).min(th),
+            scale_y,
+        )
+    };
+
+    let (mut lo, mut hi) = (0u32, size);
+    while hi - lo > window {
+        let excess = hi - lo - window;
+        let slice = excess.min((size / 16).max(1));
+        let (head, tail) = if horizontal {
+            (
+                region_entropy(&thumb, lo, 0, lo + slice, th),
+                region_entropy(&thumb, hi - slice, 0, hi, th),
+            )
+        } else {
+            (
+                region_entropy(&thumb, 0, lo, tw, lo + slice),
+                region_entropy(&thumb, 0, hi - slice, tw, hi),
+            )
+        };
+        if head < tail {
+            lo += slice;
+        } else {
+            hi -= slice;
+        }
+    }
+
+    let offset = (lo as f64 / scale).round() as u32;
+    if horizontal {
+        (offset.min(width - crop_w), 0)
     } else {
-        buffer.save(&out_path).context("Failed to save image")?;
+        (0, offset.min(height - crop_h))
+    }
+}
+
+/// Editorial stylization filters drawn with the theme colours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stylize {
+    /// Ordered-dither halftone in two tones (`bg` and `primary`).
+    Halftone,
+    /// Snap every pixel to the nearest of the first N theme colours (Oklab distance).
+    Posterize(usize),
+    /// Two misregistered spot inks (`primary`, `secondary`) on paper.
+    Riso,
+}
+
+impl Stylize {
+    fn name(&self) -> &'static str {
+        match self {
+            Stylize::Halftone => "halftone",
+            Stylize::Posterize(_) => "posterize",
+            Stylize::Riso => "riso",
+        }
+    }
+}
+
+impl FromStr for Stylize {
+    type Err = anyhow::Error;
+
+    /// Accepts `halftone`, `riso`, `posterize` or `posterize:N`.
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().split_once(':') {
+            Some(("posterize", n)) => {
+                let n: usize = n.trim().parse().context("Invalid posterize colour count")?;
+                if !(2..=themes::KEYS.len()).contains(&n) {
+                    return Err(anyhow!(
+                        "Posterize needs between 2 and {} colours",
+                        themes::KEYS.len()
+                    ));
+                }
+                Ok(Stylize::Posterize(n))
+            }
+            None if s.trim() == "posterize" => Ok(Stylize::Posterize(themes::KEYS.len())),
+            None if s.trim() == "halftone" => Ok(Stylize::Halftone),
+            None if s.trim() == "riso" => Ok(Stylize::Riso),
+            _ => Err(anyhow!("Unknown stylize filter '{}'", s)),
+        }
+    }
+}
+
+// 8x8 Bayer matrix for ordered dithering
+const BAYER_8X8: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+fn bayer_threshold(x: u32, y: u32) -> f32 {
+    (BAYER_8X8[(y % 8) as usize][(x % 8) as usize] as f32 + 0.5) / 64.0
+}
+
+/// Parse a theme colour (looked up by `key`) into Oklab.
+fn theme_oklab(key: &str, hex: Option<&String>) -> Result<Oklab> {
+    let hex = hex.ok_or_else(|| anyhow!("Theme missing '{}'", key))?;
+    let srgb = colors::parse_rgb(hex)
+        .with_context(|| format!("Invalid {} color", key))?
+        .into_format::<f32>();
+    Ok(Oklab::from_color(srgb))
+}
+
+/// The `n` theme colours a posterized image is reduced to: bg, text and primary first,
+/// then whichever remaining role is furthest from every ink already chosen.
+fn posterize_inks(theme: &BTreeMap<String, String>, n: usize) -> Result<Vec<Oklab>> {
+    let distance =
+        |a: &Oklab, b: &Oklab| (a.l - b.l).powi(2) + (a.a - b.a).powi(2) + (a.b - b.b).powi(2);
+    let mut candidates = themes::KEYS
+        .iter()
+        .map(|k| theme_oklab(k, theme.get(*k)).map(|c| (*k, c)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut inks = Vec::with_capacity(n);
+    for key in ["bg", "text", "primary"].into_iter().take(n) {
+        if let Some(pos) = candidates.iter().position(|(k, _)| *k == key) {
+            inks.push(candidates.remove(pos).1);
+        }
+    }
+    while inks.len() < n && !candidates.is_empty() {
+        let separation = |c: &Oklab| inks.iter().map(|i| distance(i, c)).fold(f32::MAX, f32::min);
+        let best = (0..candidates.len())
+            .max_by(|&a, &b| separation(&candidates[a].1).total_cmp(&separation(&candidates[b].1)))
+            .unwrap_or(0);
+        inks.push(candidates.remove(best).1);
+    }
+    Ok(inks)
+}
+
+/// Apply a stylization filter using the theme map, like `recolor_image`.
+fn apply_stylize(
+    img: &DynamicImage,
+    style: Stylize,
+    theme: &BTreeMap<String, String>,
+    seed: u64,
+) -> Result<DynamicImage> {
+    let color = |key: &str| theme_oklab(key, theme.get(key));
+
+    let rgba = img.to_rgba8();
+    let (width, height) = rgba.dimensions();
+    // Dither cells scale with the image so the pattern reads the same at any size
+    let cell = (width.min(height) / 540).max(1);
+    let to_oklab =
+        |p: &Rgba<u8>| Oklab::from_color(Srgb::new(p[0], p[1], p[2]).into_format::<f32>());
+    let to_pixel = |c: Oklab, a: u8| {
+        let srgb: Srgb<u8> = Srgb::from_color(c).clamp().into_format();
+        Rgba([srgb.red, srgb.green, srgb.blue, a])
+    };
+
+    let mut out = ImageBuffer::new(width, height);
+    match style {
+        Stylize::Halftone => {
+            let (dark, light) = (color("bg")?, color("primary")?);
+            let (lo, hi) = if dark.l <= light.l {
+                (dark, light)
+            } else {
+                (light, dark)
+            };
+            for (x, y, p) in rgba.enumerate_pixels() {
+                let l = to_oklab(p).l.clamp(0.0, 1.0);
+                let ink = if l > bayer_threshold(x / cell, y / cell) {
+                    hi
+                } else {
+                    lo
+                };
+                out.put_pixel(x, y, to_pixel(ink, p[3]));
+            }
+        }
+        Stylize::Posterize(n) => {
+            let palette = posterize_inks(theme, n)?;
+            for (x, y, p) in rgba.enumerate_pixels() {
+                let c = to_oklab(p);
+                let nearest = palette
+                    .iter()
+                    .min_by(|a, b| {
+                        let da = (a.l - c.l).powi(2) + (a.a - c.a).powi(2) + (a.b - c.b).powi(2);
+                        let db = (b.l - c.l).powi(2) + (b.a - c.a).powi(2) + (b.b - c.b).powi(2);
+                        da.total_cmp(&db)
+                    })
+                    .copied()
+                    .unwrap_or(c);
+                out.put_pixel(x, y, to_pixel(nearest, p[3]));
+            }
+        }
+        Stylize::Riso => {
+            // Print on the lighter of bg/text so the inks can darken it
+            let (bg, text) = (color("bg")?, color("text")?);
+            let paper = if bg.l >= text.l { bg } else { text };
+            let inks = [color("primary")?, color("secondary")?];
+            let paper_lin: LinSrgb = LinSrgb::from_color(paper);
+            let ink_lin: Vec<LinSrgb> = inks.iter().map(|c| LinSrgb::from_color(*c)).collect();
+
+            // Second plate is misregistered by a couple of cells
+            let offset = (cell * 3) as i64;
+            let mut rng = StdRng::seed_from_u64(seed);
+            let grain: Vec<f32> = (0..(width * height))
+                .map(|_| rng.random_range(-0.08..0.08))
+                .collect();
+
+            let coverage = |x: i64, y: i64, plate: usize| -> f32 {
+                let xc = x.clamp(0, width as i64 - 1) as u32;
+                let yc = y.clamp(0, height as i64 - 1) as u32;
+                let c = to_oklab(rgba.get_pixel(xc, yc));
+                let darkness = 1.0 - c.l.clamp(0.0, 1.0);
+                let chroma = (c.a * c.a + c.b * c.b).sqrt();
+                let raw = if plate == 0 {
+                    darkness
+                } else {
+                    (chroma * 4.0).min(1.0) * (1.0 - c.l * 0.5)
+                };
+                raw + grain[(yc * width + xc) as usize]
+            };
+
+            for (x, y, p) in rgba.enumerate_pixels() {
+                let (xi, yi) = (x as i64, y as i64);
+                let mut lin = paper_lin;
+                for (plate, ink) in ink_lin.iter().enumerate() {
+                    let (sx, sy) = if plate == 0 {
+                        (xi, yi)
+                    } else {
+                        (xi + offset, yi + offset / 2)
+                    };
+                    // Rotate the screen for the second plate to avoid moiré
+                    let threshold = if plate == 0 {
+                        bayer_threshold(x / cell, y / cell)
+                    } else {
+                        bayer_threshold(y / cell + 3, x / cell + 5)
+                    };
+                    if coverage(sx, sy, plate) > threshold {
+                        // Multiply blend, like overprinted ink
+                        let tint = LinSrgb::new(
+                            ink.red / paper_lin.red.max(1e-4),
+                            ink.green / paper_lin.green.max(1e-4),
+                            ink.blue / paper_lin.blue.max(1e-4),
+                        );
+                        lin = LinSrgb::new(
+                            lin.red * tint.red.min(1.0),
+                            lin.green * tint.green.min(1.0),
+                            lin.blue * tint.blue.min(1.0),
+                        );
+                    }
+                }
+                out.put_pixel(x, y, to_pixel(Oklab::from_color(lin), p[3]));
+            }
+        }
+    }
+
+    Ok(DynamicImage::ImageRgba8(out))
+}
+
+/// Background rendered from the theme palette when a post has no image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProceduralBackground {
+    /// Soft blobs of the accent colours over the background.
+    Mesh,
+    /// Fractal Perlin noise mapped onto a palette ramp.
+    Noise,
+    /// Low-contrast triangle grid.
+    Geometric,
+    /// Halftone dot screen fading across the canvas.
+    Halftone,
+}
+
+impl ProceduralBackground {
+    fn name(&self) -> &'static str {
+        match self {
+            ProceduralBackground::Mesh => "mesh",
+            ProceduralBackground::Noise => "noise",
+            ProceduralBackground::Geometric => "geometric",
+            ProceduralBackground::Halftone => "halftone",
+        }
+    }
+}
+
+impl FromStr for ProceduralBackground {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "mesh" => Ok(ProceduralBackground::Mesh),
+            "noise" => Ok(ProceduralBackground::Noise),
+            "geometric" => Ok(ProceduralBackground::Geometric),
+            "halftone" => Ok(ProceduralBackground::Halftone),
+            other => Err(anyhow!("Unknown background '{}'", other)),
+        }
+    }
+}
+
+/// Theme colours used by the procedural renderers, in Oklab.
+struct Swatches {
+    bg: Oklab,
+    surface: Oklab,
+    primary: Oklab,
+    secondary: Oklab,
+    accent: Oklab,
+}
+
+impl Swatches {
+    fn from_theme(theme: &HashMap<String, String>) -> Result<Self> {
+        let get = |key: &str| theme_oklab(key, theme.get(key));
+        Ok(Swatches {
+            bg: get("bg")?,
+            surface: get("surface")?,
+            primary: get("primary")?,
+            secondary: get("secondary")?,
+            accent: get("accent")?,
+        })
+    }
+}
+
+fn oklab_to_rgb(c: Oklab) -> image::Rgb<u8> {
+    let srgb: Srgb<u8> = Srgb::from_color(c).clamp().into_format();
+    image::Rgb([srgb.red, srgb.green, srgb.blue])
+}
+
+/// Stable seed derived from arbitrary text (e.g. the post title).
+pub fn stable_seed(text: &str) -> u64 {
+    fnv1a(text.as_bytes(), 0xcbf2_9ce4_8422_2325)
+}
+
+/// Render a procedural background of `size` pixels into `constants::GENERATED_DIR`.
+///
+/// The file name hashes the kind, size, seed and palette, so the same inputs always
+/// produce (and reuse) the same file.
+pub fn generate_background(
+    kind: ProceduralBackground,
+    theme: &HashMap<String, String>,
+    size: (u32, u32),
+    seed: u64,
+) -> Result<String> {
+    let swatches = Swatches::from_theme(theme)?;
+
+    let mut palette_desc: Vec<_> = theme.iter().collect();
+    palette_desc.sort();
+    let key = cache_key(
+        format!("{:?}", palette_desc).as_bytes(),
+        &format!("{:?}{:?}{}", kind, size, seed),
+    );
+
+    let dir = Path::new(crate::constants::GENERATED_DIR);
+    std::fs::create_dir_all(dir).context("Failed to create generated assets directory")?;
+    let out_path = dir.join(format!(
+        "bg-{}-{:012x}.png",
+        kind.name(),
+        key & 0xffff_ffff_ffff
+    ));
+    if out_path.exists() {
+        return Ok(out_path.to_string_lossy().to_string());
     }
-    
+
+    let (width, height) = size;
+    let img = match kind {
+        ProceduralBackground::Mesh => render_mesh(&swatches, width, height, seed),
+        ProceduralBackground::Noise => render_noise(&swatches, width, height, seed),
+        ProceduralBackground::Geometric => render_geometric(&swatches, width, height, seed),
+        ProceduralBackground::Halftone => render_halftone(&swatches, width, height, seed),
+    };
+    img.save(&out_path)
+        .context("Failed to save generated background")?;
+
     Ok(out_path.to_string_lossy().to_string())
 }
 
+fn render_mesh(sw: &Swatches, width: u32, height: u32, seed: u64) -> image::RgbImage {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    // Corners anchor the base colours; a few jittered control points carry the accents
+    let mut points = vec![
+        (0.0, 0.0, sw.bg),
+        (1.0, 0.0, sw.surface),
+        (0.0, 1.0, sw.surface),
+        (1.0, 1.0, sw.bg),
+    ];
+    for accent in [sw.primary, sw.secondary, sw.accent] {
+        let x = rng.random_range(0.15..0.85);
+        let y = rng.random_range(0.15..0.85);
+        points.push((x, y, sw.bg.mix(accent, 0.6)));
+    }
+
+    let aspect = width as f32 / height as f32;
+    image::RgbImage::from_fn(width, height, |px, py| {
+        let x = px as f32 / width as f32;
+        let y = py as f32 / height as f32;
+        let mut acc = Oklab::new(0.0, 0.0, 0.0);
+        let mut total = 0.0;
+        for (cx, cy, c) in &points {
+            let dx = (x - cx) * aspect;
+            let dy = y - cy;
+            // Shepard weighting gives smooth, mesh-gradient-like transitions
+            let w = 1.0 / (dx * dx + dy * dy + 1e-4).powf(1.5);
+            acc += *c * w;
+            total += w;
+        }
+        oklab_to_rgb(acc / total)
+    })
+}
+
+fn render_noise(sw: &Swatches, width: u32, height: u32, seed: u64) -> image::RgbImage {
+    let shape = Fbm::<Perlin>::new(seed as u32).set_octaves(5);
+    let tint = Fbm::<Perlin>::new(seed.wrapping_add(1) as u32).set_octaves(3);
+    let scale = 2.5 / width.min(height) as f64;
+
+    image::RgbImage::from_fn(width, height, |px, py| {
+        let p = [px as f64 * scale, py as f64 * scale];
+        let t = (shape.get(p) * 0.5 + 0.5).clamp(0.0, 1.0) as f32;
+        let hue_mix = (tint.get(p) * 0.5 + 0.5).clamp(0.0, 1.0) as f32;
+        let highlight = sw.primary.mix(sw.secondary, hue_mix);
+
+        // Ramp: bg → surface → tinted highlight
+        let c = if t < 0.6 {
+            sw.bg.mix(sw.surface, t / 0.6)
+        } else {
+            sw.surface
+                .mix(sw.surface.mix(highlight, 0.7), (t - 0.6) / 0.4)
+        };
+        oklab_to_rgb(c)
+    })
+}
+
+fn render_geometric(sw: &Swatches, width: u32, height: u32, seed: u64) -> image::RgbImage {
+    let cell = (width.min(height) / 8).max(1) as f32;
+    let cols = (width as f32 / cell).ceil() as usize + 1;
+    let rows = (height as f32 / cell).ceil() as usize + 1;
+
+    // One colour per triangle: a diagonal base gradient with occasional accents
+    let mut rng = StdRng::seed_from_u64(seed);
+    let accents = [sw.primary, sw.secondary, sw.accent];
+    let mut tris = Vec::with_capacity(cols * rows * 2);
+    for row in 0..rows {
+        for col in 0..cols {
+            let t = (col as f32 / cols as f32 + row as f32 / rows as f32) / 2.0;
+            for _ in 0..2 {
+                let base = sw.bg.mix(sw.surface, t + rng.random_range(-0.15..0.15));
+                let c = if rng.random::<f32>() < 0.12 {
+                    let accent = accents[rng.random_range(0..accents.len())];
+                    base.mix(accent, 0.35)
+                } else {
+                    base
+                };
+                tris.push(c);
+            }
+        }
+    }
+
+    image::RgbImage::from_fn(width, height, |px, py| {
+        let (col, row) = ((px as f32 / cell) as usize, (py as f32 / cell) as usize);
+        let fx = px as f32 / cell - col as f32;
+        let fy = py as f32 / cell - row as f32;
+        // Alternate the diagonal direction for a woven look
+        let upper = if (col + row) % 2 == 0 {
+            fx > fy
+        } else {
+            fx + fy < 1.0
+        };
+        let idx = (row * cols + col) * 2 + usize::from(upper);
+        oklab_to_rgb(tris[idx.min(tris.len() - 1)])
+    })
+}
+
+fn render_halftone(sw: &Swatches, width: u32, height: u32, seed: u64) -> image::RgbImage {
+    let spacing = (width.min(height) as f32 / 36.0).max(4.0);
+    let jitter = Fbm::<Perlin>::new(seed as u32).set_octaves(3);
+    let ink = sw.primary.mix(sw.secondary, 0.25);
+    let (sin, cos) = std::f32::consts::FRAC_PI_4.sin_cos();
+    let diagonal = (width as f32).hypot(height as f32);
+
+    image::RgbImage::from_fn(width, height, |px, py| {
+        let (x, y) = (px as f32 + 0.5, py as f32 + 0.5);
+        // Classic 45° screen
+        let u = (x * cos + y * sin) / spacing;
+        let v = (y * cos - x * sin) / spacing;
+        let (cu, cv) = (u.round(), v.round());
+        let dist = (u - cu).hypot(v - cv) * spacing;
+
+        // Dot size follows a diagonal fade perturbed by noise
+        let n = jitter.get([cu as f64 * 0.15, cv as f64 * 0.15]) as f32;
+        let tone = ((x + y) / diagonal + n * 0.35).clamp(0.0, 1.0);
+        let radius = spacing * 0.45 * tone.sqrt();
+        let coverage = (radius - dist + 0.5).clamp(0.0, 1.0);
+
+        oklab_to_rgb(sw.bg.mix(ink, coverage * 0.55))
+    })
+}
+
 pub fn generate_contours(image_path: &str) -> Result<String> {
-    let img = image::open(image_path).context("Failed to open image for contour")?;
+    let img = load_image(image_path).context("Failed to open image for contour")?;
     let gray = img.grayscale();
-    
+
     let p = Path::new(image_path);
     let root = p.parent().unwrap_or_else(|| Path::new("."));
-    let stem = p.file_stem().and_then(|s| s.to_str()).context("Invalid file stem")?;
-    
+    let stem = p
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .context("Invalid file stem")?;
+
     let output_path = root.join(format!("{}_contour.png", stem));
-    
+
     // Saving grayscale as "contour" for now. A real implementation would need edge detection kernel.
     gray.save(&output_path).context("Failed to save contour")?;
-    
+
     Ok(output_path.to_string_lossy().to_string())
 }
 
 pub fn generate_noise_contours(_root_path: &Path) -> Result<String> {
-     // TODO: Implement Perlin noise contour generation
-     Err(anyhow!("Noise contour generation not yet implemented"))
+    // TODO: Implement Perlin noise contour generation
+    Err(anyhow!("Noise contour generation not yet implemented"))
+}
+
+// Width of one cell of a contact sheet, and the gap around cells
+const SHEET_CELL: u32 = 360;
+const SHEET_GAP: u32 = 16;
+
+/// Tile `images` left to right, top to bottom into a sheet of `columns`, every
+/// cell scaled to the width and aspect of the first image.
+pub fn contact_sheet(images: &[PathBuf], columns: u32, output: &Path) -> Result<()> {
+    let first = images.first().context("No images for the contact sheet")?;
+    let (w, h) = image::image_dimensions(first)
+        .with_context(|| format!("Failed to read {}", first.display()))?;
+    let cell_h = (SHEET_CELL as f32 * h as f32 / w.max(1) as f32).round() as u32;
+    let columns = columns.clamp(1, images.len() as u32);
+    let rows = (images.len() as u32).div_ceil(columns);
+
+    let mut sheet = ImageBuffer::from_pixel(
+        columns * (SHEET_CELL + SHEET_GAP) + SHEET_GAP,
+        rows * (cell_h + SHEET_GAP) + SHEET_GAP,
+        Rgba([17u8, 17, 17, 255]),
+    );
+    for (i, path) in images.iter().enumerate() {
+        let cell = image::open(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .resize_exact(SHEET_CELL, cell_h, image::imageops::FilterType::Triangle)
+            .to_rgba8();
+        let (col, row) = (i as u32 % columns, i as u32 / columns);
+        image::imageops::overlay(
+            &mut sheet,
+            &cell,
+            (SHEET_GAP + col * (SHEET_CELL + SHEET_GAP)) as i64,
+            (SHEET_GAP + row * (cell_h + SHEET_GAP)) as i64,
+        );
+    }
+    sheet
+        .save(output)
+        .with_context(|| format!("Failed to write {}", output.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(ImageBuffer::from_fn(width, height, |x, y| {
+            Rgba([
+                (x * 7 % 256) as u8,
+                (y * 13 % 256) as u8,
+                ((x + y) % 256) as u8,
+                255,
+            ])
+        }))
+    }
+
+    #[test]
+    fn cache_key_follows_the_job() {
+        let job = |effects: Vec<Effect>, scrim| {
+            let job = BackgroundJob {
+                crop: Some((CropMode::Auto, (1080, 1350))),
+                effects,
+                stylize: None,
+                scrim,
+            };
+            format!("{:?}", job)
+        };
+        let base = cache_key(b"image", &job(vec![Effect::Blur(8.0)], None));
+
+        assert_eq!(
+            base,
+            cache_key(b"image", &job(vec![Effect::Blur(8.0)], None))
+        );
+        for other in [
+            cache_key(b"image", &job(vec![Effect::Blur(9.0)], None)),
+            cache_key(
+                b"image",
+                &job(vec![Effect::Blur(8.0), Effect::Grain(0.1)], None),
+            ),
+            cache_key(
+                b"image",
+                &job(vec![Effect::Blur(8.0)], Some(([0, 0, 0], 0.4))),
+            ),
+            cache_key(b"other", &job(vec![Effect::Blur(8.0)], None)),
+        ] {
+            assert_ne!(base, other);
+        }
+    }
+
+    #[test]
+    fn crop_window_keeps_the_target_ratio() {
+        for (size, target) in [
+            ((1600, 900), (1080, 1350)),
+            ((900, 1600), (1080, 1080)),
+            ((1000, 1000), (1080, 1920)),
+            ((1920, 1080), (1200, 630)),
+            ((1080, 1350), (1080, 1350)),
+        ] {
+            let img = gradient(size.0, size.1);
+            for mode in [
+                CropMode::Auto,
+                CropMode::Focus(0.5, 0.5),
+                CropMode::Focus(1.0, 0.0),
+            ] {
+                let (x, y, w, h) = crop_window(&img, target, mode);
+                assert!(x + w <= size.0 && y + h <= size.1, "{:?} {:?}", size, mode);
+                assert!(w == size.0 || h == size.1, "{:?} {:?}", size, mode);
+                // Within a pixel of the exact ratio
+                let expected = w as f64 * target.1 as f64 / target.0 as f64;
+                assert!((h as f64 - expected).abs() <= 1.0, "{:?} {:?}", size, mode);
+            }
+        }
+    }
+
+    #[test]
+    fn blur_with_zero_radius_is_a_no_op() {
+        let img = gradient(40, 30);
+        let out = apply_effects(&img, &[Effect::Blur(0.0)], 1);
+        assert_eq!(out.to_rgba8(), img.to_rgba8());
+
+        let mut px = vec![[0.2, 0.4, 0.6, 1.0], [0.8, 0.1, 0.0, 1.0]];
+        let original = px.clone();
+        gaussian_blur(&mut px, 2, 1, 0.0);
+        assert_eq!(px, original);
+    }
 }
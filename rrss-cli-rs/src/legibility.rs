@@ -0,0 +1,646 @@
+use anyhow::{anyhow, Context, Result};
+use image::imageops::FilterType;
+use image::RgbaImage;
+use palette::{Clamp, FromColor, Oklch, Srgb};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::colors;
+use crate::contrast::{self, Finding};
+use crate::{images, themes};
+
+// Longest side the background is reduced to before sampling
+const SAMPLE_SIZE: u32 = 256;
+// Samples per side of each text region
+const GRID: u32 = 16;
+// Share of the darkest/brightest samples allowed below the threshold,
+// so a few specks under a long title do not trigger a fix
+const TOLERANCE: f32 = 0.1;
+/// Strongest scrim the legibility fix may add.
+pub const MAX_SCRIM: f32 = 0.95;
+
+/// What to do when text over the background image is below its contrast threshold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Legibility {
+    Off,
+    /// Report the regions and keep everything as is.
+    Warn,
+    /// Strengthen the overlay with a uniform `t.bg` layer until the text passes.
+    Scrim,
+    /// Switch to the light/dark counterpart of the theme.
+    Variant,
+}
+
+impl FromStr for Legibility {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "off" | "none" => Ok(Legibility::Off),
+            "warn" => Ok(Legibility::Warn),
+            "scrim" | "overlay" => Ok(Legibility::Scrim),
+            "variant" | "theme" => Ok(Legibility::Variant),
+            other => Err(anyhow!(
+                "Invalid legibility mode '{}' (expected off, warn, scrim or variant)",
+                other
+            )),
+        }
+    }
+}
+
+/// Text drawn by a layout: theme role and area as page fractions `[x0, y0, x1, y1]`.
+struct TextRegion {
+    role: &'static str,
+    area: [f32; 4],
+}
+
+/// Where a layout puts its background and text. Mirrors the rrss-pkg layouts.
+struct Geometry {
+    /// Vertical band of the page the background fills (`y0`, `y1`).
+    frame: [f32; 2],
+    /// The image is centered in the frame; otherwise it is top-aligned.
+    centered: bool,
+    /// Opacity of a `t.bg` layer the layout draws over the image itself.
+    builtin_scrim: f32,
+    uses_overlay: bool,
+    regions: &'static [TextRegion],
+}
+
+/// Geometry of `layout`; carousel slides with their own image pass their `slide` layout.
+fn geometry(layout: &str, slide: Option<&str>) -> Geometry {
+    if let Some(kind) = slide.filter(|_| layout == "carousel") {
+        return slide_geometry(kind);
+    }
+    let card = |regions| Geometry {
+        frame: [0.0, 1.0],
+        centered: true,
+        builtin_scrim: 0.0,
+        uses_overlay: true,
+        regions,
+    };
+    match layout {
+        // Body band between the 150pt header and 100pt footer of a 1080pt page
+        "article" => Geometry {
+            frame: [150.0 / 1080.0, 980.0 / 1080.0],
+            centered: true,
+            builtin_scrim: 0.0,
+            uses_overlay: true,
+            regions: &[TextRegion {
+                role: "text",
+                area: [0.075, 0.3, 0.925, 0.75],
+            }],
+        },
+        "hero" => card(&[
+            TextRegion {
+                role: "text",
+                area: [0.075, 0.28, 0.925, 0.6],
+            },
+            TextRegion {
+                role: "muted",
+                area: [0.125, 0.6, 0.875, 0.75],
+            },
+        ]),
+        "quote" => card(&[
+            TextRegion {
+                role: "primary",
+                area: [0.4, 0.15, 0.6, 0.3],
+            },
+            TextRegion {
+                role: "text",
+                area: [0.075, 0.3, 0.925, 0.65],
+            },
+            TextRegion {
+                role: "primary",
+                area: [0.25, 0.7, 0.75, 0.8],
+            },
+        ]),
+        "stat" => card(&[
+            TextRegion {
+                role: "primary",
+                area: [0.1, 0.25, 0.9, 0.55],
+            },
+            TextRegion {
+                role: "muted",
+                area: [0.1, 0.55, 0.9, 0.75],
+            },
+        ]),
+        // Split draws on solid fills only
+        "split" => card(&[]),
+        // The carousel stretches the image over every slide under a 90% `t.bg` layer
+        "carousel" => Geometry {
+            frame: [0.0, 1.0],
+            centered: false,
+            builtin_scrim: 0.9,
+            uses_overlay: false,
+            regions: &[TextRegion {
+                role: "text",
+                area: [0.1, 0.3, 0.9, 0.7],
+            }],
+        },
+        _ => card(&[TextRegion {
+            role: "text",
+            area: [0.075, 0.25, 0.925, 0.75],
+        }]),
+    }
+}
+
+/// A carousel slide over its own image, which covers the page. Every slide
+/// layout but `image` draws a 90% `t.bg` layer over it; `image` keeps the photo
+/// bare and sets its caption on a `t.surface` box.
+fn slide_geometry(kind: &str) -> Geometry {
+    let slide = |builtin_scrim, regions| Geometry {
+        frame: [0.0, 1.0],
+        centered: true,
+        builtin_scrim,
+        uses_overlay: false,
+        regions,
+    };
+    match kind {
+        "cover" => slide(
+            0.9,
+            &[
+                TextRegion {
+                    role: "text",
+                    area: [0.3, 0.04, 0.7, 0.12],
+                },
+                TextRegion {
+                    role: "text",
+                    area: [0.1, 0.25, 0.9, 0.75],
+                },
+                TextRegion {
+                    role: "primary",
+                    area: [0.3, 0.88, 0.7, 0.96],
+                },
+            ],
+        ),
+        "quote" => slide(
+            0.9,
+            &[
+                TextRegion {
+                    role: "primary",
+                    area: [0.4, 0.15, 0.6, 0.3],
+                },
+                TextRegion {
+                    role: "text",
+                    area: [0.1, 0.3, 0.9, 0.7],
+                },
+            ],
+        ),
+        "image" => slide(0.0, &[]),
+        // `content`: a small header over the left-aligned body
+        _ => slide(
+            0.9,
+            &[
+                TextRegion {
+                    role: "text",
+                    area: [0.075, 0.04, 0.6, 0.12],
+                },
+                TextRegion {
+                    role: "text",
+                    area: [0.075, 0.15, 0.925, 0.85],
+                },
+            ],
+        ),
+    }
+}
+
+/// Overlay layer as the layouts draw it.
+enum Overlay {
+    /// A recolored SVG gradient: viewBox aspect (h / w), gradient vector and
+    /// `(offset, color, opacity)` stops. `None` colors are the `#000000` marker
+    /// `recolor-svg` swaps for `t.bg`.
+    Gradient {
+        aspect: f32,
+        vector: [f32; 4],
+        stops: Vec<(f32, Option<[u8; 3]>, f32)>,
+    },
+    /// A raster image stretched over the frame.
+    Raster(RgbaImage),
+}
+
+fn svg_attr<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let needle = format!(" {}=\"", name);
+    let start = tag.find(&needle)? + needle.len();
+    let len = tag[start..].find('"')?;
+    Some(&tag[start..start + len])
+}
+
+fn svg_number(value: &str) -> Option<f32> {
+    match value.trim().strip_suffix('%') {
+        Some(pct) => pct.trim().parse::<f32>().ok().map(|v| v / 100.0),
+        None => value.trim().parse().ok(),
+    }
+}
+
+/// Opening tags named `name`, without the closing `>`.
+fn svg_tags<'a>(svg: &'a str, name: &str) -> Vec<&'a str> {
+    let open = format!("<{}", name);
+    svg.match_indices(&open)
+        .filter_map(|(i, _)| {
+            let end = svg[i..].find('>')?;
+            Some(&svg[i..i + end])
+        })
+        .collect()
+}
+
+/// Read the single full-size linear gradient the bundled overlays are made of.
+fn parse_svg_overlay(svg: &str) -> Option<Overlay> {
+    let root = *svg_tags(svg, "svg").first()?;
+    let aspect = match svg_attr(root, "viewBox") {
+        Some(vb) => {
+            let v: Vec<f32> = vb
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter_map(|x| x.parse().ok())
+                .collect();
+            (v.len() == 4 && v[2] > 0.0).then(|| v[3] / v[2])?
+        }
+        None => svg_number(svg_attr(root, "height")?)? / svg_number(svg_attr(root, "width")?)?,
+    };
+
+    let gradient = *svg_tags(svg, "linearGradient").first()?;
+    let coord = |name: &str, default: f32| {
+        svg_attr(gradient, name)
+            .and_then(svg_number)
+            .unwrap_or(default)
+    };
+    let vector = [
+        coord("x1", 0.0),
+        coord("y1", 0.0),
+        coord("x2", 1.0),
+        coord("y2", 0.0),
+    ];
+
+    let stops: Vec<_> = svg_tags(svg, "stop ")
+        .into_iter()
+        .map(|stop| {
+            let offset = svg_attr(stop, "offset").and_then(svg_number).unwrap_or(0.0);
+            let color = svg_attr(stop, "stop-color")
+                .filter(|c| !c.eq_ignore_ascii_case("#000000"))
+                .and_then(|c| colors::parse_rgb(c).ok())
+                .map(|c| [c.red, c.green, c.blue]);
+            let opacity = svg_attr(stop, "stop-opacity")
+                .and_then(svg_number)
+                .unwrap_or(1.0);
+            (offset, color, opacity)
+        })
+        .collect();
+    if stops.is_empty() {
+        return None;
+    }
+
+    Some(Overlay::Gradient {
+        aspect,
+        vector,
+        stops,
+    })
+}
+
+fn load_overlay(path: &str) -> Result<Overlay> {
+    if path.ends_with(".svg") {
+        let svg = std::fs::read_to_string(path).context("Failed to read overlay")?;
+        parse_svg_overlay(&svg).ok_or_else(|| anyhow!("Unsupported overlay SVG {:?}", path))
+    } else {
+        Ok(Overlay::Raster(images::load_image(path)?.to_rgba8()))
+    }
+}
+
+/// One point under a text region: background pixel (with alpha) and the
+/// overlay color/opacity above it.
+struct Sample {
+    pixel: [u8; 4],
+    overlay: Option<(Option<[u8; 3]>, f32)>,
+}
+
+/// Background image and overlay sampled under the text regions of a layout.
+/// Sampling does not depend on the theme, so variants and scrims are cheap to test.
+pub struct Scene {
+    geometry: Geometry,
+    /// Samples per region, across every slide.
+    samples: Vec<Vec<Sample>>,
+}
+
+/// Nearest-pixel lookup of a normalized position, `None` outside the image.
+fn pixel_at(img: &RgbaImage, u: f32, v: f32) -> Option<[u8; 4]> {
+    if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+        return None;
+    }
+    let x = (u * img.width() as f32) as u32;
+    let y = (v * img.height() as f32) as u32;
+    Some(img.get_pixel(x, y).0)
+}
+
+fn overlay_at(
+    overlay: &Overlay,
+    u: f32,
+    v: f32,
+    frame_aspect: f32,
+) -> Option<(Option<[u8; 3]>, f32)> {
+    match overlay {
+        Overlay::Raster(img) => {
+            pixel_at(img, u, v).map(|p| (Some([p[0], p[1], p[2]]), p[3] as f32 / 255.0))
+        }
+        Overlay::Gradient {
+            aspect,
+            vector,
+            stops,
+        } => {
+            // Scaled to the frame width and centered, like `recolor-svg(.., width: 100%)`
+            let shown = aspect / frame_aspect;
+            let v = (v - (1.0 - shown) / 2.0) / shown;
+            if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+                return None;
+            }
+            let [x1, y1, x2, y2] = *vector;
+            let (dx, dy) = (x2 - x1, y2 - y1);
+            let len2 = (dx * dx + dy * dy).max(1e-6);
+            let t = (((u - x1) * dx + (v - y1) * dy) / len2).clamp(0.0, 1.0);
+
+            let last = stops.len() - 1;
+            let i = stops.iter().position(|s| s.0 >= t).unwrap_or(last);
+            if i == 0 || stops[i].0 <= stops[i - 1].0 {
+                return Some((stops[i].1, stops[i].2));
+            }
+            let (a, b) = (&stops[i - 1], &stops[i]);
+            let k = (t - a.0) / (b.0 - a.0);
+            let color = if k < 0.5 { a.1 } else { b.1 };
+            Some((color, a.2 + (b.2 - a.2) * k))
+        }
+    }
+}
+
+/// How a layout scales the background image into its frame.
+struct Placement {
+    frame: [f32; 2],
+    slides: u32,
+    /// Frame height over width; the carousel frame spans every slide.
+    frame_aspect: f32,
+    /// Image height in frame heights (the image is scaled to the frame width).
+    shown: f32,
+    /// Top of the image in frame heights.
+    top: f32,
+}
+
+impl Placement {
+    fn new(
+        geometry: &Geometry,
+        layout: &str,
+        platform: &str,
+        slides: u32,
+        image_aspect: f32,
+    ) -> Result<Self> {
+        let (page_w, page_h) = *crate::constants::get_platforms()
+            .get(platform)
+            .ok_or_else(|| anyhow!("Unknown platform '{}'", platform))?;
+        let slides = if layout == "carousel" {
+            slides.max(1)
+        } else {
+            1
+        };
+
+        let [f0, f1] = geometry.frame;
+        let frame_w = page_w as f32 * slides as f32;
+        let frame_h = page_h as f32 * (f1 - f0);
+        let frame_aspect = frame_h / frame_w;
+        let shown = image_aspect / frame_aspect;
+        let top = if geometry.centered {
+            (1.0 - shown) / 2.0
+        } else {
+            0.0
+        };
+        Ok(Placement {
+            frame: geometry.frame,
+            slides,
+            frame_aspect,
+            shown,
+            top,
+        })
+    }
+
+    /// Page point `(x, y)` of `slide` as `(u, v)` in the frame and `v` in the image.
+    fn project(&self, slide: u32, x: f32, y: f32) -> (f32, f32, f32) {
+        let [f0, f1] = self.frame;
+        let u = (slide as f32 + x) / self.slides as f32;
+        let v = (y - f0) / (f1 - f0);
+        (u, v, (v - self.top) / self.shown)
+    }
+}
+
+/// Areas of the background image that `layout` covers with text, as image fractions
+/// `[x0, y0, x1, y1]`, for an image of height / width `image_aspect`.
+pub fn text_areas(
+    layout: &str,
+    platform: &str,
+    slides: u32,
+    image_aspect: f32,
+) -> Result<Vec<[f32; 4]>> {
+    let geometry = geometry(layout, None);
+    let placement = Placement::new(&geometry, layout, platform, slides, image_aspect)?;
+    let mut areas = Vec::new();
+    for region in geometry.regions {
+        let [x0, y0, x1, y1] = region.area;
+        for slide in 0..placement.slides {
+            let (u0, _, v0) = placement.project(slide, x0, y0);
+            let (u1, _, v1) = placement.project(slide, x1, y1);
+            areas.push([u0, v0, u1, v1]);
+        }
+    }
+    Ok(areas)
+}
+
+impl Scene {
+    /// Sample `image_path` as `layout` places it on `platform`, with `overlay` on top,
+    /// over the listed `frames` of the carousel (every frame when `None`).
+    /// A carousel slide drawn over its own image passes its `slide_layout`.
+    /// Overlays that cannot be analysed are reported and left out.
+    pub fn new(
+        image_path: &str,
+        overlay: Option<&str>,
+        layout: &str,
+        slide_layout: Option<&str>,
+        platform: &str,
+        slides: u32,
+        frames: Option<&[u32]>,
+    ) -> Result<Self> {
+        let geometry = geometry(layout, slide_layout);
+        let img =
+            images::load_image(image_path)?.resize(SAMPLE_SIZE, SAMPLE_SIZE, FilterType::Triangle);
+        let img = img.to_rgba8();
+        let placement = Placement::new(
+            &geometry,
+            layout,
+            platform,
+            slides,
+            img.height() as f32 / img.width() as f32,
+        )?;
+
+        let overlay = match overlay.filter(|_| geometry.uses_overlay) {
+            Some(path) => match load_overlay(path) {
+                Ok(o) => Some(o),
+                Err(e) => {
+                    eprintln!("⚠ Legibility check ignores overlay: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let mut samples = Vec::new();
+        for region in geometry.regions {
+            let mut points = Vec::new();
+            let shown = (0..placement.slides).filter(|s| frames.is_none_or(|f| f.contains(s)));
+            for slide in shown {
+                for gy in 0..GRID {
+                    for gx in 0..GRID {
+                        let [x0, y0, x1, y1] = region.area;
+                        let x = x0 + (x1 - x0) * (gx as f32 + 0.5) / GRID as f32;
+                        let y = y0 + (y1 - y0) * (gy as f32 + 0.5) / GRID as f32;
+                        let (u, v, v_image) = placement.project(slide, x, y);
+
+                        points.push(Sample {
+                            pixel: pixel_at(&img, u, v_image).unwrap_or([0; 4]),
+                            overlay: overlay
+                                .as_ref()
+                                .and_then(|o| overlay_at(o, u, v, placement.frame_aspect)),
+                        });
+                    }
+                }
+            }
+            samples.push(points);
+        }
+
+        Ok(Scene { geometry, samples })
+    }
+
+    /// Effective contrast of each text region: the worst sample once the darkest
+    /// or brightest `TOLERANCE` share is left out. `scrim` adds a `t.bg` layer.
+    pub fn check(&self, theme: &HashMap<String, String>, scrim: f32) -> Result<Vec<Finding>> {
+        let bg = theme_rgb(theme, "bg")?;
+        // The theme's `overlay-opacity` takes the place of the layout's own layer
+        let layer = themes::overlay_opacity(theme).unwrap_or(self.geometry.builtin_scrim);
+        let mut findings = Vec::new();
+
+        for (region, samples) in self.geometry.regions.iter().zip(&self.samples) {
+            let pairing = match contrast::pairing(region.role, "bg") {
+                Some(p) => p,
+                None => continue,
+            };
+            let fg = theme_rgb(theme, region.role)?;
+            let fg = Srgb::new(fg[0], fg[1], fg[2]) / 255.0;
+
+            let mut ratios = Vec::with_capacity(samples.len());
+            let mut lcs = Vec::with_capacity(samples.len());
+            for sample in samples {
+                let c = composite(sample, bg, layer, scrim);
+                let c = Srgb::new(c[0], c[1], c[2]) / 255.0;
+                ratios.push(contrast::wcag_ratio(fg, c));
+                lcs.push(contrast::apca_lc(fg, c).abs());
+            }
+            findings.push(Finding {
+                pairing,
+                ratio: percentile(&mut ratios),
+                lc: percentile(&mut lcs),
+            });
+        }
+        Ok(findings)
+    }
+
+    /// Lowest scrim opacity (in 5% steps) that makes every region pass,
+    /// or `None` if even `MAX_SCRIM` is not enough.
+    pub fn required_scrim(&self, theme: &HashMap<String, String>) -> Result<Option<f32>> {
+        for step in 1..=(MAX_SCRIM * 20.0).round() as u32 {
+            let alpha = step as f32 / 20.0;
+            if self.check(theme, alpha)?.iter().all(|f| f.passes()) {
+                return Ok(Some(alpha));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// How far `findings` are from passing, summed over regions as the missing
+/// fraction of each threshold. Zero when everything passes.
+pub fn shortfall(findings: &[Finding]) -> f32 {
+    findings
+        .iter()
+        .map(|f| {
+            (1.0 - f.ratio / f.pairing.min_ratio).max(0.0)
+                + (1.0 - f.lc / f.pairing.min_lc).max(0.0)
+        })
+        .sum()
+}
+
+fn theme_rgb(theme: &HashMap<String, String>, key: &str) -> Result<[f32; 3]> {
+    let hex = theme
+        .get(key)
+        .ok_or_else(|| anyhow!("Theme missing '{}'", key))?;
+    let c = colors::parse_rgb(hex).with_context(|| format!("Invalid {} color", key))?;
+    Ok([c.red as f32, c.green as f32, c.blue as f32])
+}
+
+fn mix(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+    ]
+}
+
+/// Color seen under the text, in paint order: the frame's `t.bg` fill, the image
+/// with the extra scrim baked into it, the overlay, then the layout's own `t.bg` layer.
+fn composite(sample: &Sample, bg: [f32; 3], builtin: f32, scrim: f32) -> [f32; 3] {
+    let p = sample.pixel;
+    let image = mix([p[0] as f32, p[1] as f32, p[2] as f32], bg, scrim);
+    let mut c = mix(bg, image, p[3] as f32 / 255.0);
+    if let Some((color, alpha)) = sample.overlay {
+        let color = color.map_or(bg, |o| [o[0] as f32, o[1] as f32, o[2] as f32]);
+        c = mix(c, color, alpha);
+    }
+    mix(c, bg, builtin)
+}
+
+fn percentile(values: &mut [f32]) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    values[((values.len() as f32 * TOLERANCE) as usize).min(values.len() - 1)]
+}
+
+/// Named light/dark pairs among the built-in themes.
+const COUNTERPARTS: &[(&str, &str)] = &[
+    ("dark", "light"),
+    ("gruvbox-dark", "gruvbox-light"),
+    ("solarized-dark", "solarized-light"),
+    ("catppuccin-mocha", "catppuccin-latte"),
+];
+
+/// The light/dark counterpart of `name`: its named pair when one is available,
+/// otherwise `theme` with the neutral roles mirrored in Oklch lightness.
+pub fn counterpart(
+    name: &str,
+    theme: &HashMap<String, String>,
+    palettes: &HashMap<String, HashMap<String, String>>,
+) -> Result<(String, HashMap<String, String>)> {
+    let paired = COUNTERPARTS.iter().find_map(|&(a, b)| match name {
+        n if n == a => Some(b),
+        n if n == b => Some(a),
+        _ => None,
+    });
+    if let Some(map) = paired.and_then(|p| palettes.get(p).map(|m| (p, m))) {
+        return Ok((map.0.to_string(), map.1.clone()));
+    }
+
+    let mut mirrored = theme.clone();
+    for key in ["bg", "surface", "text", "muted"] {
+        if let Some(hex) = theme.get(key) {
+            let c = colors::parse_rgb(hex)
+                .with_context(|| format!("Invalid {} color", key))?
+                .into_format::<f32>();
+            let mut lch = Oklch::from_color(c);
+            lch.l = (1.0 - lch.l).clamp(0.05, 0.98);
+            let c: Srgb<u8> = Srgb::from_color(lch).clamp().into_format();
+            mirrored.insert(key.to_string(), colors::to_hex(c.into()));
+        }
+    }
+    Ok((format!("{}-inverted", name), mirrored))
+}
@@ -3,7 +3,13 @@ pub mod cli;
 pub mod colors;
 pub mod config;
 pub mod constants;
+pub mod contrast;
+pub mod cvd;
+pub mod export;
 pub mod generate;
 pub mod images;
+pub mod legibility;
+pub mod palette_engine;
+pub mod rotation;
 pub mod templates;
 pub mod themes;
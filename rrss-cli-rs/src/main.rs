@@ -1,9 +1,14 @@
 use anyhow::{Context, Result};
 use clap::Parser;
-use rrss_cli_rs::{build, cli, colors, config, generate};
+use rrss_cli_rs::{
+    build, cli, colors, config, constants, contrast, cvd, export, generate, images, legibility,
+    palette_engine, themes,
+};
 
+use palette::{FromColor, Oklab, Oklch};
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 fn main() -> Result<()> {
     let parsed = cli::Cli::parse();
@@ -14,64 +19,110 @@ fn main() -> Result<()> {
             base_color,
             format,
             name,
-        } => cmd_colors(base_color, format, name),
+            output,
+            harmony,
+            preview,
+            palette_engine,
+            contrast,
+            config,
+        } => cmd_colors(
+            base_color,
+            format,
+            name,
+            output,
+            *harmony,
+            preview,
+            palette_engine,
+            *contrast,
+            config,
+        ),
 
         cli::Commands::Extract {
             image,
             count,
             format,
             suggest_accent,
-            name: _,
-        } => cmd_extract(image, *count, format, *suggest_accent),
+            name,
+            palette_format,
+            output,
+            palette_engine,
+            layout,
+            platform,
+            keep_extremes,
+            contrast,
+            config,
+        } => cmd_extract(
+            image,
+            *count,
+            format,
+            *suggest_accent,
+            name,
+            palette_format,
+            output,
+            palette_engine,
+            &extract_options(image, layout.as_deref(), platform, *keep_extremes)?,
+            *contrast,
+            config,
+        ),
+
+        cli::Commands::Themes { command } => match command {
+            cli::ThemeCommands::Export {
+                name,
+                format,
+                output,
+                config,
+            } => cmd_themes_export(name, format, output, config),
+            cli::ThemeCommands::List { config } => cmd_themes_list(config),
+            cli::ThemeCommands::Show { name, config } => cmd_themes_show(name, config),
+            cli::ThemeCommands::Preview {
+                themes,
+                layout,
+                platform,
+                image,
+                columns,
+                ppi,
+                config,
+                output_dir,
+            } => cmd_themes_preview(
+                &root, themes, layout, platform, image, *columns, *ppi, config, output_dir,
+            ),
+            cli::ThemeCommands::Diff { a, b, config } => cmd_themes_diff(a, b, config),
+            cli::ThemeCommands::Mix {
+                a,
+                b,
+                t,
+                steps,
+                name,
+                output,
+                config,
+            } => cmd_themes_mix(a, b, *t, *steps, name, output, config),
+            cli::ThemeCommands::FromLogo {
+                logo,
+                name,
+                count,
+                output,
+            } => cmd_themes_from_logo(&root, logo, name, *count, output),
+        },
 
         cli::Commands::Generate {
-            brand,
-            title,
-            quote,
-            image,
-            logo,
-            overlay,
-            accent,
-            auto_accent,
-            url,
-            platform,
-            layout,
-            theme,
-            author,
-            source,
-            tag,
+            post,
             slides,
             contour,
             output,
-            font_heading,
-            font_body,
-            font_mono,
         } => {
-            let params = cli::GenerateParams {
-                brand: brand.clone(),
-                title: title.clone(),
-                quote: quote.clone(),
-                image: image.clone(),
-                logo: logo.clone(),
-                overlay: overlay.clone(),
-                accent: accent.clone(),
-                auto_accent: *auto_accent,
-                url: url.clone(),
-                platform: platform.clone(),
-                layout: layout.clone(),
-                theme: theme.clone(),
-                author: author.clone(),
-                source: source.clone(),
-                tag: tag.clone(),
-                slides: slides.clone(),
-                contour: *contour,
-                font_heading: font_heading.clone(),
-                font_body: font_body.clone(),
-                font_mono: font_mono.clone(),
-            };
-            let content = generate::do_generate(&params, None)?;
+            let (cfg, mut params) = post_params(post)?;
+            params.slides = slides
+                .iter()
+                .flat_map(|s| s.split('|'))
+                .map(|text| config::Slide::from(text.trim()))
+                .collect();
+            params.contour = *contour;
+            let content = generate::do_generate(&params, cfg.as_ref())?;
             fs::write(output, content).context("Failed to write output file")?;
-            println!("✓ Generated {} ({}, {})", output, layout, platform);
+            println!(
+                "✓ Generated {} ({}, {})",
+                output, params.layout, params.platform
+            );
             Ok(())
         }
 
@@ -84,52 +135,23 @@ fn main() -> Result<()> {
             format,
         } => cmd_compile(&root, files, *ppi, output_dir, *all, template, format),
 
-        cli::Commands::Full {
-            brand,
-            title,
-            quote,
-            image,
-            logo,
-            overlay,
-            accent,
-            auto_accent,
-            url,
-            platform,
-            layout,
+        cli::Commands::Proof {
+            files,
+            cvd,
+            severity,
             theme,
-            author,
-            source,
-            tag,
+            config,
+            output_dir,
+        } => cmd_proof(&root, files, cvd, *severity, theme, config, output_dir),
+
+        cli::Commands::Full {
+            post,
             ppi,
             output_name,
-            font_heading,
-            font_body,
-            font_mono,
             format,
         } => {
-            let params = cli::GenerateParams {
-                brand: brand.clone(),
-                title: title.clone(),
-                quote: quote.clone(),
-                image: image.clone(),
-                logo: logo.clone(),
-                overlay: overlay.clone(),
-                accent: accent.clone(),
-                auto_accent: *auto_accent,
-                url: url.clone(),
-                platform: platform.clone(),
-                layout: layout.clone(),
-                theme: theme.clone(),
-                author: author.clone(),
-                source: source.clone(),
-                tag: tag.clone(),
-                slides: None,
-                contour: false,
-                font_heading: font_heading.clone(),
-                font_body: font_body.clone(),
-                font_mono: font_mono.clone(),
-            };
-            let content = generate::do_generate(&params, None)?;
+            let (cfg, params) = post_params(post)?;
+            let content = generate::do_generate(&params, cfg.as_ref())?;
             let typ_file = format!("{}.typ", output_name);
             fs::write(&typ_file, content).context("Failed to write typ file")?;
 
@@ -166,61 +188,739 @@ fn main() -> Result<()> {
 
 // ─── Command handlers ──────────────────────────────────────────────────────────
 
-fn cmd_colors(base_color: &str, format: &cli::OutputFormat, name: &str) -> Result<()> {
-    let palette = colors::generate_palette(base_color);
-    match format {
-        cli::OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&palette)?),
-        cli::OutputFormat::Typst => {
-            println!("  {}: (", name);
-            let mut keys: Vec<_> = palette.keys().collect();
-            keys.sort();
-            for key in keys {
-                println!("    {}: rgb(\"{}\"),", key, palette[key]);
-            }
-            println!("  ),");
-        }
-        cli::OutputFormat::Table => {
-            println!("Palette: {}", base_color);
-            for (k, v) in &palette {
-                println!("{}: {}", k, v);
-            }
+#[allow(clippy::too_many_arguments)]
+fn cmd_colors(
+    base_color: &str,
+    format: &cli::OutputFormat,
+    name: &str,
+    output: &Option<PathBuf>,
+    harmony: Option<colors::Harmony>,
+    preview: &Option<PathBuf>,
+    engine: &[String],
+    contrast: contrast::ContrastMode,
+    config_file: &Path,
+) -> Result<()> {
+    let cfg = load_config(config_file)?;
+    let builder = palette_engine::builder(cfg.as_ref(), engine)?;
+    let mut palette = match harmony {
+        Some(h) => colors::harmony_palette(base_color, h)?,
+        None => colors::generate_palette(base_color, None, builder)?,
+    };
+    contrast::enforce(&mut palette, contrast, name)?;
+    if let cli::OutputFormat::Table = format {
+        match harmony {
+            Some(h) => println!(
+                "Palette: {} ({})",
+                base_color,
+                format!("{:?}", h).to_lowercase()
+            ),
+            None => println!("Palette: {}", base_color),
         }
     }
+    write_palette(&palette, name, format, output)?;
+    if preview.is_some() {
+        write_palette(&palette, name, &cli::OutputFormat::Png, preview)?;
+    }
+    Ok(())
+}
+
+/// Print or save a palette through the shared exporters.
+/// Binary formats default to `<name>.<ext>` instead of the terminal.
+fn write_palette(
+    palette: &HashMap<String, String>,
+    name: &str,
+    format: &cli::OutputFormat,
+    output: &Option<PathBuf>,
+) -> Result<()> {
+    let data = export::render(palette, name, format)?;
+    let path = match output {
+        Some(p) => p.clone(),
+        None if export::is_binary(format) => {
+            PathBuf::from(format!("{}.{}", name, export::extension(format)))
+        }
+        None => {
+            print!("{}", String::from_utf8_lossy(&data));
+            return Ok(());
+        }
+    };
+    fs::write(&path, data).context("Failed to write palette file")?;
+    println!("✓ Palette written to {}", path.display());
     Ok(())
 }
 
+/// `rrss extract --format json` output; fields and palette roles keep their order.
+#[derive(serde::Serialize)]
+struct Analysis<'a> {
+    name: &'a str,
+    image: &'a str,
+    colors: Vec<serde_json::Value>,
+    palette: export::Ordered<'a>,
+}
+
+#[allow(clippy::too_many_arguments)]
 fn cmd_extract(
     image: &std::path::PathBuf,
     count: u8,
     format: &cli::ExtractFormat,
     suggest_accent: bool,
+    name: &str,
+    palette_format: &cli::OutputFormat,
+    output: &Option<PathBuf>,
+    engine: &[String],
+    options: &colors::ExtractOptions,
+    contrast: contrast::ContrastMode,
+    config_file: &Path,
 ) -> Result<()> {
-    println!("Analyzing {:?}...", image);
+    eprintln!("Analyzing {:?}...", image);
     let img_str = image.to_str().context("Invalid image path")?;
+    let cfg = load_config(config_file)?;
+    let builder = palette_engine::builder(cfg.as_ref(), engine)?;
 
     if suggest_accent {
-        let accent = colors::suggest_accent(img_str);
+        let accent = colors::suggest_accent(img_str, &builder);
         println!("Suggested accent: {}", accent);
         return Ok(());
     }
 
-    let extracted = colors::extract_from_image(img_str, count)?;
+    let extracted = colors::extract_weighted(img_str, count, options)?;
+    let base = extracted.first().context("No colors extracted")?.hex();
     match format {
-        cli::ExtractFormat::Json => println!("JSON output not fully implemented"),
+        cli::ExtractFormat::Json => {
+            let mut palette = colors::generate_palette(&base, None, builder)?;
+            contrast::enforce(&mut palette, contrast, &base)?;
+            let roles: Vec<(String, Oklab)> = palette
+                .iter()
+                .filter_map(|(k, v)| Some((k.clone(), colors::hex_oklab(v)?)))
+                .collect();
+
+            let entries: Vec<serde_json::Value> = extracted
+                .iter()
+                .map(|swatch| {
+                    let hex = swatch.hex();
+                    let c = swatch.color;
+                    let lab = colors::hex_oklab(&hex).unwrap_or_default();
+                    let lch = Oklch::from_color(lab);
+                    // The derived palette role this color sits closest to
+                    let role = roles
+                        .iter()
+                        .min_by(|a, b| {
+                            colors::oklab_distance(a.1, lab)
+                                .total_cmp(&colors::oklab_distance(b.1, lab))
+                        })
+                        .map(|(k, _)| k.as_str());
+                    serde_json::json!({
+                        "hex": hex,
+                        "rgb": [c.red, c.green, c.blue],
+                        "oklch": {
+                            "l": colors::round3(lch.l),
+                            "c": colors::round3(lch.chroma),
+                            "h": colors::round3(lch.hue.into_positive_degrees()),
+                        },
+                        "share": colors::round3(swatch.share * 100.0),
+                        "role": role,
+                    })
+                })
+                .collect();
+
+            let out = Analysis {
+                name,
+                image: img_str,
+                colors: entries,
+                palette: export::Ordered::new(&palette),
+            };
+            let json = serde_json::to_string_pretty(&out)? + "\n";
+            match output {
+                Some(path) => {
+                    fs::write(path, json).context("Failed to write JSON file")?;
+                    println!("✓ Analysis written to {}", path.display());
+                }
+                None => print!("{}", json),
+            }
+        }
         cli::ExtractFormat::Palette => {
-            let base = format!(
-                "#{:02x}{:02x}{:02x}",
-                extracted[0].r, extracted[0].g, extracted[0].b
-            );
-            let palette = colors::generate_palette(&base);
-            println!("Palette from {}:", base);
-            for (k, v) in palette {
-                println!("{}: {}", k, v);
+            let mut palette = colors::generate_palette(&base, None, builder)?;
+            contrast::enforce(&mut palette, contrast, &base)?;
+            if let cli::OutputFormat::Table = palette_format {
+                println!("Palette from {}:", base);
             }
+            write_palette(&palette, name, palette_format, output)?;
         }
         cli::ExtractFormat::Table => {
-            for (i, c) in extracted.iter().enumerate() {
-                println!("{}: #{:02x}{:02x}{:02x}", i + 1, c.r, c.g, c.b);
+            for (i, swatch) in extracted.iter().enumerate() {
+                println!("{}: {} ({:.1}%)", i + 1, swatch.hex(), swatch.share * 100.0);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Pixel weighting for `rrss extract`: the text areas of `layout` count less,
+/// and near-black / near-white pixels are left out unless `keep_extremes`.
+fn extract_options(
+    image: &Path,
+    layout: Option<&str>,
+    platform: &str,
+    keep_extremes: bool,
+) -> Result<colors::ExtractOptions> {
+    let text_areas = match layout {
+        Some(layout) => {
+            let (width, height) =
+                images::oriented_dimensions(image.to_str().context("Invalid image path")?)?;
+            let aspect = height as f32 / width.max(1) as f32;
+            legibility::text_areas(layout, platform, 1, aspect)?
+        }
+        None => Vec::new(),
+    };
+    Ok(colors::ExtractOptions {
+        text_areas,
+        ignore_extremes: !keep_extremes,
+        ..Default::default()
+    })
+}
+
+fn cmd_themes_export(
+    name: &str,
+    format: &cli::OutputFormat,
+    output: &Option<PathBuf>,
+    config_file: &Path,
+) -> Result<()> {
+    let cfg = load_config(config_file)?;
+    let palettes = themes::predefined::get_theme_palettes(cfg.as_ref());
+    let palette = palettes
+        .get(name)
+        .with_context(|| format!("Unknown theme '{}'", name))?;
+    write_palette(palette, name, format, output)
+}
+
+fn load_config(config_file: &Path) -> Result<Option<config::Config>> {
+    if config_file.exists() {
+        Ok(Some(config::Config::load(config_file)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Config and brand kit for `generate` and `full`. The config is read whenever the
+/// file exists; the kit is the one `--brand-kit` names, or else `[defaults] brand_kit`.
+fn load_brand_kit(
+    name: &Option<String>,
+    config_file: &Path,
+) -> Result<(Option<config::Config>, config::BrandKit)> {
+    let cfg = match name {
+        // A kit asked for by name needs its file
+        Some(_) => Some(config::Config::load(config_file)?),
+        None => load_config(config_file)?,
+    };
+    let kit_name = name.as_deref().or_else(|| {
+        cfg.as_ref()
+            .and_then(|c| c.defaults.get("brand_kit"))
+            .and_then(|v| v.as_str())
+    });
+    let kit = match (kit_name, &cfg) {
+        (Some(kit_name), Some(cfg)) => cfg.brand_kit(kit_name)?.clone(),
+        _ => config::BrandKit::default(),
+    };
+    Ok((cfg, kit))
+}
+
+/// Generation parameters from the options `generate` and `full` share, with the
+/// brand kit filling in what the command line leaves out.
+fn post_params(post: &cli::PostArgs) -> Result<(Option<config::Config>, cli::GenerateParams)> {
+    let (cfg, kit) = load_brand_kit(&post.brand_kit, &post.config)?;
+    let pick =
+        |cli: &Option<String>, from_kit: &Option<String>| cli.clone().or_else(|| from_kit.clone());
+    let accent = pick(&post.accent, &kit.accent).unwrap_or_else(|| "theme".to_string());
+    let params = cli::GenerateParams {
+        brand: pick(&post.brand, &kit.brand).unwrap_or_default(),
+        title: post.title.clone(),
+        quote: post.quote.clone(),
+        image: post.image.clone(),
+        logo: pick(&post.logo, &kit.logo),
+        overlay: pick(&post.overlay, &kit.overlay),
+        auto_accent: post.auto_accent || accent == "auto",
+        accent,
+        url: pick(&post.url, &kit.url).unwrap_or_default(),
+        platform: post.platform.clone(),
+        layout: post.layout.clone(),
+        theme: pick(&post.theme, &kit.theme).unwrap_or_else(|| "dark".to_string()),
+        post_index: post.index,
+        date: post.date.clone(),
+        author: post.author.clone(),
+        source: post.source.clone(),
+        tag: post.tag.clone(),
+        slides: Vec::new(),
+        contour: false,
+        crop: post.crop,
+        effects: post.effects.clone(),
+        background: post.background,
+        seed: post.seed,
+        stylize: post.stylize,
+        contrast: post.contrast,
+        legibility: post.legibility,
+        variant: post.variant,
+        material_variant: post.material_variant,
+        palette_engine: post.palette_engine.clone(),
+        font_heading: pick(&post.font_heading, &kit.font_heading),
+        font_body: pick(&post.font_body, &kit.font_body),
+        font_mono: pick(&post.font_mono, &kit.font_mono),
+    };
+    Ok((cfg, params))
+}
+
+/// Truecolor block for `hex`, or nothing when stdout is not a terminal.
+fn swatch(hex: &str, width: usize) -> String {
+    use std::io::IsTerminal;
+    match colors::parse_rgb(hex) {
+        Ok(c) if std::io::stdout().is_terminal() => format!(
+            "\x1b[48;2;{};{};{}m{}\x1b[0m",
+            c.red,
+            c.green,
+            c.blue,
+            " ".repeat(width)
+        ),
+        _ => String::new(),
+    }
+}
+
+fn cmd_themes_list(config_file: &Path) -> Result<()> {
+    let cfg = load_config(config_file)?;
+    let palettes = themes::predefined::get_theme_palettes(cfg.as_ref());
+    let names: std::collections::BTreeSet<&String> = palettes.keys().collect();
+
+    for name in names {
+        let palette = &palettes[name];
+        let strip: String = themes::KEYS
+            .iter()
+            .filter_map(|k| palette.get(*k))
+            .map(|hex| swatch(hex, 2))
+            .collect();
+        let light = palette
+            .get("bg")
+            .and_then(|bg| colors::hex_oklab(bg))
+            .is_some_and(|bg| bg.l > 0.6);
+        let line = format!(
+            "{:<20} {:<11} {:<6} {}",
+            name,
+            themes::predefined::origin(cfg.as_ref(), name),
+            if light { "light" } else { "dark" },
+            strip
+        );
+        println!("{}", line.trim_end());
+    }
+    println!(
+        "\nGenerated from the image: auto, auto-matugen (add -light or -dark to pin the variant)"
+    );
+    Ok(())
+}
+
+fn cmd_themes_show(name: &str, config_file: &Path) -> Result<()> {
+    let cfg = load_config(config_file)?;
+    let palettes = themes::predefined::get_theme_palettes(cfg.as_ref());
+    let palette = palettes
+        .get(name)
+        .with_context(|| format!("Unknown theme '{}'", name))?;
+
+    println!(
+        "{} ({})\n",
+        name,
+        themes::predefined::origin(cfg.as_ref(), name)
+    );
+    for (key, hex) in themes::KEYS
+        .iter()
+        .filter_map(|k| palette.get(*k).map(|v| (*k, v)))
+    {
+        let lch = colors::hex_oklab(hex)
+            .map(Oklch::from_color)
+            .unwrap_or_default();
+        println!(
+            "  {} {:<10} {}  oklch({:.3} {:.3} {:.1})",
+            swatch(hex, 4),
+            key,
+            hex,
+            lch.l,
+            lch.chroma,
+            lch.hue.into_positive_degrees()
+        );
+    }
+
+    let style: Vec<(&str, &String)> = themes::STYLE_KEYS
+        .iter()
+        .filter_map(|k| palette.get(*k).map(|v| (*k, v)))
+        .collect();
+    if !style.is_empty() {
+        println!("\nStyle");
+        for (key, value) in style {
+            println!("  {:<16} {}", key, value);
+        }
+    }
+
+    println!("\nContrast");
+    for finding in contrast::audit(palette)? {
+        let mark = if finding.passes() { "✓" } else { "✗" };
+        println!("  {} {}", mark, finding);
+    }
+    Ok(())
+}
+
+fn cmd_themes_diff(a: &str, b: &str, config_file: &Path) -> Result<()> {
+    let cfg = load_config(config_file)?;
+    let palettes = themes::predefined::get_theme_palettes(cfg.as_ref());
+    let get = |name: &str| {
+        palettes
+            .get(name)
+            .with_context(|| format!("Unknown theme '{}'", name))
+    };
+    let (pa, pb) = (get(a)?, get(b)?);
+
+    println!("{} → {}\n", a, b);
+    let deltas = themes::differences(pa, pb)?;
+    for (key, delta) in &deltas {
+        // Below 1 the difference is not visible; below INDISTINGUISHABLE it is subtle
+        let note = if *delta < 1.0 {
+            "same"
+        } else if *delta < cvd::INDISTINGUISHABLE {
+            "close"
+        } else {
+            ""
+        };
+        println!(
+            "  {:<10} {} {}  →  {} {}   ΔE {:>5.1}  {}",
+            key,
+            swatch(&pa[*key], 2),
+            pa[*key],
+            swatch(&pb[*key], 2),
+            pb[*key],
+            delta,
+            note
+        );
+    }
+    let mean = deltas.iter().map(|(_, d)| d).sum::<f32>() / deltas.len() as f32;
+    println!("\n  mean ΔE {:.1}", mean);
+
+    let style: Vec<&str> = themes::STYLE_KEYS
+        .iter()
+        .copied()
+        .filter(|k| pa.get(*k) != pb.get(*k))
+        .collect();
+    if !style.is_empty() {
+        println!("\nStyle");
+        let show = |v: Option<&String>| v.cloned().unwrap_or_else(|| "-".to_string());
+        for key in style {
+            println!(
+                "  {:<16} {}  →  {}",
+                key,
+                show(pa.get(key)),
+                show(pb.get(key))
+            );
+        }
+    }
+    Ok(())
+}
+
+fn cmd_themes_mix(
+    a: &str,
+    b: &str,
+    t: f32,
+    steps: Option<u32>,
+    name: &Option<String>,
+    output: &Option<PathBuf>,
+    config_file: &Path,
+) -> Result<()> {
+    let cfg = load_config(config_file)?;
+    let palettes = themes::predefined::get_theme_palettes(cfg.as_ref());
+    let get = |name: &str| {
+        palettes
+            .get(name)
+            .with_context(|| format!("Unknown theme '{}'", name))
+    };
+    let (pa, pb) = (get(a)?, get(b)?);
+    if !(0.0..=1.0).contains(&t) {
+        anyhow::bail!("--t must be between 0 and 1 (got {})", t);
+    }
+
+    // One theme at `t`, or `steps` themes evenly spaced from `a` to `b`
+    let points: Vec<f32> = match steps {
+        Some(n) if n >= 2 => (0..n).map(|i| i as f32 / (n - 1) as f32).collect(),
+        Some(_) => anyhow::bail!("--steps needs at least 2 themes"),
+        None => vec![t],
+    };
+    let mut out = String::new();
+    for (i, point) in points.iter().enumerate() {
+        let theme = themes::mix(pa, pb, *point)?;
+        let theme_name = match (name, steps) {
+            (Some(n), Some(_)) => format!("{}-{}", n, i + 1),
+            (Some(n), None) => n.clone(),
+            (None, _) => format!("{}-{}-{}", a, b, (point * 100.0).round()),
+        };
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("[themes.{}]\n", theme_name));
+        for key in themes::KEYS {
+            out.push_str(&format!("{} = \"{}\"\n", key, theme[key]));
+        }
+        for key in themes::STYLE_KEYS {
+            if let Some(value) = theme.get(key) {
+                out.push_str(&format!("{} = \"{}\"\n", key, value));
+            }
+        }
+    }
+
+    match output {
+        Some(path) => {
+            fs::write(path, &out).with_context(|| format!("Failed to write {}", path.display()))?;
+            println!("✓ {} theme(s) written to {}", points.len(), path.display());
+        }
+        None => print!("{}", out),
+    }
+    Ok(())
+}
+
+/// Render a sample post in every theme and tile the results into one sheet.
+#[allow(clippy::too_many_arguments)]
+fn cmd_themes_preview(
+    root: &Path,
+    only: &[String],
+    layout: &str,
+    platform: &str,
+    image: &Option<String>,
+    columns: u32,
+    ppi: u32,
+    config_file: &Path,
+    output_dir: &Path,
+) -> Result<()> {
+    let cfg = load_config(config_file)?;
+    let palettes = themes::predefined::get_theme_palettes(cfg.as_ref());
+    let mut names: Vec<&String> = if only.is_empty() {
+        palettes.keys().collect()
+    } else {
+        for name in only {
+            if !palettes.contains_key(name) {
+                anyhow::bail!("Unknown theme '{}'", name);
+            }
+        }
+        only.iter().collect()
+    };
+    names.sort();
+    let output_dir = &root.join(output_dir);
+    fs::create_dir_all(output_dir)?;
+
+    let mut rendered = Vec::new();
+    for name in names {
+        let params = cli::GenerateParams {
+            brand: "rrss".to_string(),
+            // The theme name doubles as the label of its cell
+            title: name.clone(),
+            quote: "Lo que se ve bien se lee mejor.".to_string(),
+            image: image.clone(),
+            logo: None,
+            overlay: None,
+            accent: "theme".to_string(),
+            auto_accent: false,
+            url: String::new(),
+            platform: platform.to_string(),
+            layout: layout.to_string(),
+            theme: name.clone(),
+            post_index: 0,
+            date: None,
+            author: String::new(),
+            source: None,
+            tag: None,
+            slides: Vec::new(),
+            contour: false,
+            crop: None,
+            effects: Vec::new(),
+            background: None,
+            seed: None,
+            stylize: None,
+            // Each theme is shown as defined
+            contrast: Some(contrast::ContrastMode::Off),
+            legibility: Some(legibility::Legibility::Off),
+            variant: None,
+            material_variant: None,
+            palette_engine: Vec::new(),
+            font_heading: None,
+            font_body: None,
+            font_mono: None,
+        };
+
+        let typ_file = root.join(format!("_preview-{}.typ", name));
+        let output_path = output_dir.join(format!("{}.png", name));
+        let result = generate::do_generate(&params, cfg.as_ref())
+            .and_then(|content| Ok(fs::write(&typ_file, content)?))
+            .and_then(|_| build::run_typst_compile(root, &typ_file, &output_path, ppi));
+        let _ = fs::remove_file(&typ_file);
+        match result {
+            Ok(_) => {
+                println!("  ✓ {}", name);
+                rendered.push(output_path);
+            }
+            Err(e) => println!("  ✗ {}: {}", name, e),
+        }
+    }
+
+    if rendered.is_empty() {
+        anyhow::bail!("No theme could be rendered");
+    }
+    let sheet = output_dir.join("preview.png");
+    images::contact_sheet(&rendered, columns, &sheet)?;
+    println!("✓ Contact sheet written to {}", sheet.display());
+    Ok(())
+}
+
+fn cmd_themes_from_logo(
+    root: &Path,
+    logo: &Path,
+    name: &Option<String>,
+    count: u8,
+    output: &Option<PathBuf>,
+) -> Result<()> {
+    let name = match name {
+        Some(n) => n.clone(),
+        None => logo
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_lowercase())
+            .context("Invalid logo path")?,
+    };
+    let is_svg = logo
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("svg"));
+
+    // Typst rasterizes SVG logos; other formats are read directly
+    let raster = if is_svg {
+        let typ_file = root.join(format!("_logo-{}.typ", name));
+        let png = std::env::temp_dir().join(format!("rrss-logo-{}.png", name));
+        // Absolute paths in Typst are relative to --root
+        let relative = logo.strip_prefix(root).unwrap_or(logo);
+        let source = format!(
+            "#set page(width: auto, height: auto, margin: 0pt, fill: none)\n#image(\"/{}\", width: {}pt)\n",
+            relative.to_string_lossy().replace('\\', "/"),
+            constants::LOGO_RASTER_SIZE
+        );
+        let result = fs::write(&typ_file, source)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| build::run_typst_compile(root, &typ_file, &png, 72));
+        let _ = fs::remove_file(&typ_file);
+        result.with_context(|| format!("Failed to rasterize {}", logo.display()))?;
+        png
+    } else {
+        logo.to_path_buf()
+    };
+
+    // Logos are small, so every pixel counts: no text areas, and near-black or
+    // near-white ink is only dropped when there is colour besides it
+    let options = colors::ExtractOptions {
+        text_weight: 1.0,
+        ..Default::default()
+    };
+    let swatches = colors::extract_weighted(&raster.to_string_lossy(), count, &options);
+    if is_svg {
+        let _ = fs::remove_file(&raster);
+    }
+    let brand = themes::brand_colors(&swatches?);
+
+    let hexes: Vec<String> = brand.iter().map(|c| colors::to_hex((*c).into())).collect();
+    let mut out = format!(
+        "# Themes from {} (brand colours: {})\n",
+        logo.display(),
+        hexes.join(", ")
+    );
+    for (variant, dark) in [("dark", true), ("light", false)] {
+        let theme = themes::from_brand(&brand, dark)
+            .with_context(|| format!("Failed to build the {} theme", variant))?;
+        out.push_str(&format!("\n[themes.{}-{}]\n", name, variant));
+        for key in themes::KEYS {
+            out.push_str(&format!("{} = \"{}\"\n", key, theme[key]));
+        }
+    }
+
+    match output {
+        Some(path) => {
+            fs::write(path, &out).with_context(|| format!("Failed to write {}", path.display()))?;
+            println!(
+                "✓ Themes {}-dark and {}-light written to {}",
+                name,
+                name,
+                path.display()
+            );
+        }
+        None => print!("{}", out),
+    }
+    Ok(())
+}
+
+fn cmd_proof(
+    root: &Path,
+    files: &[PathBuf],
+    kinds: &[cvd::Cvd],
+    severity: f32,
+    theme: &Option<String>,
+    config_file: &Path,
+    output_dir: &Path,
+) -> Result<()> {
+    let cfg = load_config(config_file)?;
+    let kinds = if kinds.is_empty() {
+        cvd::ALL.to_vec()
+    } else {
+        kinds.to_vec()
+    };
+    let theme_name = theme.clone().unwrap_or_else(|| {
+        cfg.as_ref()
+            .and_then(|c| c.defaults.get("theme"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("dark")
+            .to_string()
+    });
+    let palettes = themes::predefined::get_theme_palettes(cfg.as_ref());
+
+    let targets = if files.is_empty() {
+        let mut found = Vec::new();
+        let out = root.join("output");
+        if out.exists() {
+            for entry in walkdir::WalkDir::new(out).max_depth(1) {
+                let entry = entry?;
+                if entry.path().extension().is_some_and(|e| e == "png") {
+                    found.push(entry.path().to_path_buf());
+                }
+            }
+        }
+        found.sort();
+        found
+    } else {
+        files.to_vec()
+    };
+    let output_dir = &root.join(output_dir);
+    if !targets.is_empty() {
+        fs::create_dir_all(output_dir)?;
+    }
+
+    for kind in kinds {
+        println!("\n{} (severity {:.1})", kind, severity);
+
+        match palettes.get(&theme_name) {
+            Some(palette) => {
+                let pairs = cvd::collapsed_pairs(palette, kind, severity)?;
+                if pairs.is_empty() {
+                    println!("  ✓ Theme '{}': every key pair stays distinct", theme_name);
+                }
+                for (a, b, normal, simulated) in pairs {
+                    println!(
+                        "  ⚠ Theme '{}': {} / {} become indistinguishable (ΔE00 {:.1} → {:.1})",
+                        theme_name, a, b, normal, simulated
+                    );
+                }
+            }
+            None => println!(
+                "  ⚠ Theme '{}' has no fixed palette; check the rendered images instead",
+                theme_name
+            ),
+        }
+
+        for target in &targets {
+            let output_path = cvd::proof_path(target, output_dir, kind);
+            match cvd::simulate_image(target, &output_path, kind, severity) {
+                Ok(_) => println!("  ✓ {}", output_path.display()),
+                Err(e) => println!("  ✗ {}: {}", target.display(), e),
             }
         }
     }
@@ -242,7 +942,7 @@ fn cmd_compile(
         if content_dir.exists() {
             for entry in walkdir::WalkDir::new(content_dir).max_depth(1) {
                 let entry = entry?;
-                if entry.path().extension().map_or(false, |e| e == "typ") {
+                if entry.path().extension().is_some_and(|e| e == "typ") {
                     found.push(entry.path().to_path_buf());
                 }
             }
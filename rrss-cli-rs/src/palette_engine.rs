@@ -0,0 +1,235 @@
+use anyhow::{anyhow, Context, Result};
+use palette::OklabHue;
+use thaimeleon_lib::scheme_builder::{ChromaBuilder, SchemeBuilder, ThemeConfig};
+
+use crate::colors;
+use crate::config::Config;
+
+/// Named sets of overrides on top of the default scheme builder.
+/// Keys use the same paths as `[palette_engine]`.
+const PRESETS: &[(&str, &[(&str, f32)])] = &[
+    ("default", &[]),
+    (
+        "vivid",
+        &[
+            ("chroma_weight_priority", 0.03),
+            ("red_chroma_minimum", 0.16),
+            ("orange_chroma_minimum", 0.12),
+            ("yellow_chroma_minimum", 0.12),
+            ("green_chroma_minimum", 0.1),
+        ],
+    ),
+    (
+        "muted",
+        &[
+            ("chroma_weight_priority", 0.005),
+            ("red_chroma_minimum", 0.05),
+            ("orange_chroma_minimum", 0.04),
+            ("yellow_chroma_minimum", 0.04),
+            ("green_chroma_minimum", 0.04),
+            ("bg_neutral_chroma_builder.chroma_intercept", 0.02),
+            ("fg_neutral_chroma_builder.chroma_intercept", 0.01),
+        ],
+    ),
+    (
+        "high-contrast",
+        &[
+            ("light.set_3_dps_contrast", 50.0),
+            ("light.set_4_dps_contrast", 75.0),
+            ("light.set_5_dps_contrast", 90.0),
+            ("dark.set_3_dps_contrast", 40.0),
+            ("dark.set_4_dps_contrast", 80.0),
+            ("dark.set_5_dps_contrast", 90.0),
+            ("dark.base_lightness_maximum", 0.12),
+        ],
+    ),
+    (
+        "deep",
+        &[
+            ("light_theme_threshold", 0.7),
+            ("dark.base_lightness_minimum", 0.05),
+            ("dark.base_lightness_maximum", 0.1),
+            ("dark.surface_distance", 0.05),
+        ],
+    ),
+];
+
+/// One step of palette engine tuning: a preset or a single value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Setting {
+    Preset(String),
+    Value(String, f32),
+}
+
+/// Names of the built-in presets.
+pub fn preset_names() -> Vec<&'static str> {
+    PRESETS.iter().map(|(name, _)| *name).collect()
+}
+
+/// Parse `vivid`, `dark.surface_distance=0.08` or a comma separated mix of both.
+pub fn parse_spec(spec: &str) -> Result<Vec<Setting>> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| match item.split_once('=') {
+            Some((key, value)) => {
+                let value = value
+                    .trim()
+                    .parse::<f32>()
+                    .map_err(|_| anyhow!("Invalid value for '{}': '{}'", key.trim(), value))?;
+                Ok(Setting::Value(key.trim().to_string(), value))
+            }
+            None => Ok(Setting::Preset(item.to_string())),
+        })
+        .collect()
+}
+
+/// Settings of a `[palette_engine]` table: `preset` first, then every numeric value
+/// under its dotted path (`dark.base_lightness_maximum`).
+pub fn table_settings(table: &toml::Table) -> Result<Vec<Setting>> {
+    fn walk(table: &toml::Table, prefix: &str, out: &mut Vec<Setting>) -> Result<()> {
+        for (key, value) in table {
+            let path = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            match value {
+                toml::Value::Table(t) => walk(t, &path, out)?,
+                toml::Value::Float(f) => out.push(Setting::Value(path, *f as f32)),
+                toml::Value::Integer(i) => out.push(Setting::Value(path, *i as f32)),
+                toml::Value::String(s) if path == "preset" => {
+                    out.insert(0, Setting::Preset(s.clone()))
+                }
+                other => return Err(anyhow!("Invalid value for '{}': {}", path, other)),
+            }
+        }
+        Ok(())
+    }
+
+    let mut out = Vec::new();
+    walk(table, "", &mut out)?;
+    // Keys for both schemes first, so `dark.` and `light.` ones refine them
+    out.sort_by_key(
+        |s| matches!(s, Setting::Value(k, _) if k.starts_with("dark.") || k.starts_with("light.")),
+    );
+    Ok(out)
+}
+
+/// Set one field of a scheme builder. Keys without a `dark.` or `light.` prefix
+/// apply to both schemes.
+fn set(builder: &mut SchemeBuilder, key: &str, value: f32) -> Result<()> {
+    match key {
+        "k_means_count" => builder.k_means_count = value.round().max(1.0) as _,
+        "light_theme_threshold" => builder.light_theme_threshold = value as _,
+        _ => {
+            let (schemes, field) = match key.split_once('.') {
+                Some(("dark", field)) => (vec![&mut builder.dark], field),
+                Some(("light", field)) => (vec![&mut builder.light], field),
+                _ => (vec![&mut builder.light, &mut builder.dark], key),
+            };
+            for scheme in schemes {
+                if !set_theme_field(scheme, field, value) {
+                    return Err(anyhow!("Unknown palette engine setting '{}'", key));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn set_theme_field(cfg: &mut ThemeConfig, field: &str, value: f32) -> bool {
+    if let Some((chroma, f)) = field.split_once('.') {
+        return match chroma {
+            "bg_neutral_chroma_builder" => {
+                set_chroma_field(&mut cfg.bg_neutral_chroma_builder, f, value)
+            }
+            "fg_neutral_chroma_builder" => {
+                set_chroma_field(&mut cfg.fg_neutral_chroma_builder, f, value)
+            }
+            _ => false,
+        };
+    }
+
+    // Every plain numeric field of `ThemeConfig`, matched by name
+    macro_rules! fields {
+        ($($name:ident),* $(,)?) => {
+            match field {
+                $(stringify!($name) => cfg.$name = value as _,)*
+                _ => return false,
+            }
+        };
+    }
+    fields!(
+        base_lightness_minimum,
+        base_lightness_maximum,
+        surface_distance,
+        set_2_dps_contrast,
+        set_2_lightness_correction,
+        set_3_dps_contrast,
+        set_4_dps_contrast,
+        set_5_dps_contrast,
+        neutral_chroma_blend,
+        prefered_hue_angle,
+        minimum_hue_angle,
+        chroma_weight_priority,
+        penalty_weight_priority,
+        maximum_accent_hue_center_translation,
+        high_contrast_fg_accent_radius_baseline,
+        fg_accent_radius_baseline,
+        rg_accent_radius_baseline,
+        bg_accent_radius_baseline,
+        red_chroma_minimum,
+        orange_chroma_minimum,
+        yellow_chroma_minimum,
+        green_chroma_minimum,
+    );
+    true
+}
+
+fn set_chroma_field(chroma: &mut ChromaBuilder, field: &str, value: f32) -> bool {
+    match field {
+        "chroma_intercept" => chroma.chroma_intercept = value as _,
+        "lightness_to_chroma_slope" => chroma.lightness_to_chroma_slope = value as _,
+        "low_point_chroma_intercept" => chroma.low_point_chroma_intercept = value as _,
+        // In degrees
+        "hue_low_point" => chroma.hue_low_point = OklabHue::from_degrees(value as _),
+        _ => return false,
+    }
+    true
+}
+
+/// Apply `settings` in order to `builder`.
+pub fn apply(builder: &mut SchemeBuilder, settings: &[Setting]) -> Result<()> {
+    for setting in settings {
+        match setting {
+            Setting::Preset(name) => {
+                let (_, values) = PRESETS.iter().find(|(n, _)| n == name).ok_or_else(|| {
+                    anyhow!(
+                        "Unknown palette engine preset '{}' (expected {})",
+                        name,
+                        preset_names().join(", ")
+                    )
+                })?;
+                for (key, value) in values.iter() {
+                    set(builder, key, *value)?;
+                }
+            }
+            Setting::Value(key, value) => set(builder, key, *value)?,
+        }
+    }
+    Ok(())
+}
+
+/// Scheme builder for the automatic palettes: the defaults, then the config's
+/// `[palette_engine]` section, then `spec` (from the CLI or the post).
+pub fn builder(cfg: Option<&Config>, spec: &[String]) -> Result<SchemeBuilder> {
+    let mut builder = colors::default_builder();
+    if let Some(table) = cfg.and_then(|c| c.palette_engine.as_ref()) {
+        let settings = table_settings(table)?;
+        apply(&mut builder, &settings).context("Invalid [palette_engine]")?;
+    }
+    let settings = parse_spec(&spec.join(","))?;
+    apply(&mut builder, &settings)?;
+    Ok(builder)
+}
@@ -0,0 +1,317 @@
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+
+/// What advances a theme rotation from one entry to the next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cycle {
+    /// Position of the post in `posts.toml`.
+    Index,
+    Day,
+    /// Weeks starting on Monday.
+    Week,
+    Month,
+    /// Meteorological seasons, starting with winter (December–February).
+    Season,
+    /// The day split into as many equal slots as there are themes.
+    Time,
+}
+
+impl FromStr for Cycle {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "index" | "post" => Ok(Cycle::Index),
+            "day" | "daily" => Ok(Cycle::Day),
+            "week" | "weekly" => Ok(Cycle::Week),
+            "month" | "monthly" => Ok(Cycle::Month),
+            "season" | "seasonal" => Ok(Cycle::Season),
+            "time" | "hour" => Ok(Cycle::Time),
+            other => Err(anyhow!(
+                "Invalid rotation '{}' (expected index, day, week, month, season or time)",
+                other
+            )),
+        }
+    }
+}
+
+/// A date and hour, as given in a post's `date`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Moment {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+}
+
+impl FromStr for Moment {
+    type Err = anyhow::Error;
+
+    /// `2026-10-19`, `2026-10-19T18:30` or `2026-10-19 18:30`; a missing time is midnight.
+    /// Seconds and offsets after the minutes (as in TOML datetimes) are ignored.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || anyhow!("Invalid date '{}' (expected YYYY-MM-DD[THH:MM])", s);
+        let s = s.trim();
+        let (date, time) = match s.split_once(['T', ' ']) {
+            Some((date, time)) => (date, Some(time)),
+            None => (s, None),
+        };
+        let mut parts = date.splitn(3, '-');
+        let mut next = || parts.next().ok_or_else(invalid);
+        let year: i64 = next()?.parse().map_err(|_| invalid())?;
+        let month: u32 = next()?.parse().map_err(|_| invalid())?;
+        let day: u32 = next()?.parse().map_err(|_| invalid())?;
+        let hour: u32 = match time {
+            Some(t) => {
+                let mut fields = t.splitn(3, ':');
+                let hour = fields.next().and_then(|h| h.parse().ok());
+                let minute = fields.next().and_then(|m| m.get(..2)?.parse::<u32>().ok());
+                match (hour, minute) {
+                    (Some(h), Some(m)) if m < 60 => h,
+                    _ => return Err(invalid()),
+                }
+            }
+            None => 0,
+        };
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) || hour > 23 {
+            return Err(invalid());
+        }
+        Ok(Moment {
+            year,
+            month,
+            day,
+            hour,
+        })
+    }
+}
+
+impl Moment {
+    /// Days since 1970-01-01.
+    pub fn days(&self) -> i64 {
+        days_from_civil(self.year, self.month, self.day)
+    }
+}
+
+// Days since 1970-01-01 in the proleptic Gregorian calendar (Howard Hinnant's algorithm)
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    (days_from_civil(next_year, next_month, 1) - days_from_civil(year, month, 1)) as u32
+}
+
+/// `theme = "rotate:[nord, forest, sunset]"`: a list of themes picked in a fixed order,
+/// by post index unless a cycle is named (`rotate:week:[…]`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rotation {
+    pub cycle: Cycle,
+    pub themes: Vec<String>,
+}
+
+impl Rotation {
+    /// The rotation in `theme`, or `None` when it names a single theme.
+    pub fn parse(theme: &str) -> Result<Option<Rotation>> {
+        let Some(rest) = theme.trim().strip_prefix("rotate:") else {
+            return Ok(None);
+        };
+        let (cycle, list) = match rest.split_once('[') {
+            Some(("", list)) => (Cycle::Index, list),
+            Some((cycle, list)) => (cycle.trim().trim_end_matches(':').parse()?, list),
+            None => return Err(anyhow!("Invalid rotation '{}': missing [themes]", theme)),
+        };
+        let list = list
+            .trim_end()
+            .strip_suffix(']')
+            .ok_or_else(|| anyhow!("Invalid rotation '{}': missing ']'", theme))?;
+        let themes: Vec<String> = list
+            .split(',')
+            .map(|t| t.trim().trim_matches('"').to_string())
+            .filter(|t| !t.is_empty())
+            .collect();
+        if themes.is_empty() {
+            return Err(anyhow!("Invalid rotation '{}': no themes", theme));
+        }
+        Ok(Some(Rotation { cycle, themes }))
+    }
+
+    /// Whether the theme depends on the post's date rather than its position.
+    pub fn by_date(&self) -> bool {
+        self.cycle != Cycle::Index
+    }
+
+    /// The theme for the post at `index` published on `date`. Date cycles need a
+    /// date, so the same post always gets the same theme.
+    pub fn pick(&self, index: usize, date: Option<&str>) -> Result<&str> {
+        let moment = || -> Result<Moment> {
+            date.ok_or_else(|| {
+                anyhow!(
+                    "Theme rotation by {} needs a date: set `date` on the post or in [defaults], or pass --date",
+                    format!("{:?}", self.cycle).to_lowercase()
+                )
+            })?
+            .parse()
+        };
+        let len = self.themes.len() as i64;
+        let step = match self.cycle {
+            Cycle::Index => index as i64,
+            Cycle::Day => moment()?.days(),
+            // 1970-01-01 was a Thursday
+            Cycle::Week => (moment()?.days() + 3).div_euclid(7),
+            Cycle::Month => {
+                let m = moment()?;
+                m.year * 12 + m.month as i64 - 1
+            }
+            Cycle::Season => (moment()?.month % 12 / 3) as i64,
+            Cycle::Time => moment()?.hour as i64 * len / 24,
+        };
+        Ok(&self.themes[step.rem_euclid(len) as usize])
+    }
+}
+
+/// The theme `theme` stands for on the post at `index`: the one a `rotate:` list
+/// picks, or `theme` itself.
+pub fn theme_for(theme: &str, index: usize, date: Option<&str>) -> Result<String> {
+    match Rotation::parse(theme)? {
+        Some(rotation) => Ok(rotation.pick(index, date)?.to_string()),
+        None => Ok(theme.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_count_from_the_epoch() {
+        for (date, days) in [
+            ((1970, 1, 1), 0),
+            ((1969, 12, 31), -1),
+            ((2000, 3, 1), 11_017),
+            ((2024, 2, 29), 19_782),
+            ((2026, 10, 19), 20_745),
+        ] {
+            assert_eq!(days_from_civil(date.0, date.1, date.2), days, "{:?}", date);
+        }
+    }
+
+    #[test]
+    fn months_have_their_length() {
+        assert_eq!(days_in_month(2026, 1), 31);
+        assert_eq!(days_in_month(2026, 2), 28);
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(1900, 2), 28);
+        assert_eq!(days_in_month(2000, 2), 29);
+        assert_eq!(days_in_month(2026, 4), 30);
+        assert_eq!(days_in_month(2026, 12), 31);
+    }
+
+    #[test]
+    fn moments_parse() {
+        let at = |year, month, day, hour| Moment {
+            year,
+            month,
+            day,
+            hour,
+        };
+        for (input, expected) in [
+            ("2026-10-19", at(2026, 10, 19, 0)),
+            ("2026-10-19T18:30", at(2026, 10, 19, 18)),
+            ("2026-10-19 07:05", at(2026, 10, 19, 7)),
+            ("2026-10-19T18:30:00Z", at(2026, 10, 19, 18)),
+            (" 2024-02-29 ", at(2024, 2, 29, 0)),
+        ] {
+            assert_eq!(input.parse::<Moment>().unwrap(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn impossible_moments_are_rejected() {
+        for input in [
+            "2026-02-29",
+            "2026-02-31",
+            "2026-04-31",
+            "2026-13-01",
+            "2026-00-10",
+            "2026-10-00",
+            "2026-10-19T24:00",
+            "2026-10-19T18:60",
+            "2026-10-19T18",
+            "2026-10",
+            "19/10/2026",
+        ] {
+            assert!(input.parse::<Moment>().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn rotations_parse() {
+        assert_eq!(Rotation::parse("nord").unwrap(), None);
+        assert_eq!(
+            Rotation::parse("rotate:[nord, \"forest\" ,sunset]").unwrap(),
+            Some(Rotation {
+                cycle: Cycle::Index,
+                themes: vec!["nord".into(), "forest".into(), "sunset".into()],
+            })
+        );
+        assert_eq!(
+            Rotation::parse("rotate:week:[nord, dracula]")
+                .unwrap()
+                .map(|r| r.cycle),
+            Some(Cycle::Week)
+        );
+        for invalid in [
+            "rotate:[]",
+            "rotate:nord",
+            "rotate:[nord",
+            "rotate:yearly:[nord]",
+        ] {
+            assert!(Rotation::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn rotations_pick_in_order() {
+        let themes = |cycle| Rotation {
+            cycle,
+            themes: vec!["a".into(), "b".into(), "c".into()],
+        };
+        assert_eq!(themes(Cycle::Index).pick(4, None).unwrap(), "b");
+        // 2026-10-19 is a Monday, the 20745th day since the epoch
+        assert_eq!(themes(Cycle::Day).pick(0, Some("2026-10-19")).unwrap(), "a");
+        assert_eq!(
+            themes(Cycle::Week).pick(0, Some("2026-10-18")).unwrap(),
+            "c"
+        );
+        assert_eq!(
+            themes(Cycle::Week).pick(0, Some("2026-10-19")).unwrap(),
+            "a"
+        );
+        assert_eq!(
+            themes(Cycle::Season).pick(0, Some("2026-01-15")).unwrap(),
+            "a"
+        );
+        assert_eq!(
+            themes(Cycle::Season).pick(0, Some("2026-07-15")).unwrap(),
+            "c"
+        );
+        assert_eq!(
+            themes(Cycle::Time)
+                .pick(0, Some("2026-10-19T20:00"))
+                .unwrap(),
+            "c"
+        );
+        assert!(themes(Cycle::Month).pick(0, None).is_err());
+    }
+}
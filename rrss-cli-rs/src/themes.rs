@@ -1,15 +1,531 @@
+use anyhow::{anyhow, Context, Result};
+use palette::{Clamp, FromColor, Mix, Oklab, Oklch, Srgb};
+use std::collections::HashMap;
+
+use crate::{colors, contrast};
+
+/// Roles every theme defines, in the order palettes are listed and exported.
+pub const KEYS: [&str; 8] = [
+    "bg",
+    "surface",
+    "muted",
+    "text",
+    "primary",
+    "secondary",
+    "accent",
+    "highlight",
+];
+
+/// Map a base16 (or base24, whose first 16 slots are the same) scheme onto the theme roles.
+pub fn map_base16(colors: &[&str]) -> HashMap<String, String> {
+    let mut theme = HashMap::new();
+    theme.insert("bg".to_string(), colors[0].to_string());
+    theme.insert("surface".to_string(), colors[1].to_string());
+    theme.insert("muted".to_string(), colors[3].to_string());
+    theme.insert("text".to_string(), colors[5].to_string());
+    theme.insert("primary".to_string(), colors[8].to_string());
+    theme.insert("highlight".to_string(), colors[9].to_string());
+    theme.insert("accent".to_string(), colors[12].to_string()); // base0C/D
+    theme.insert("secondary".to_string(), colors[14].to_string()); // base0E
+    theme
+}
+
+/// Optional roles that style the layouts beyond the palette. Theme tables hold them
+/// as strings; `style_typst` turns them into Typst values.
+pub const STYLE_KEYS: [&str; 9] = [
+    "bg-gradient",
+    "radius",
+    "overlay-opacity",
+    "weight-heading",
+    "weight-body",
+    "spacing",
+    "font-heading",
+    "font-body",
+    "font-mono",
+];
+
+const WEIGHTS: [&str; 9] = [
+    "thin",
+    "extralight",
+    "light",
+    "regular",
+    "medium",
+    "semibold",
+    "bold",
+    "extrabold",
+    "black",
+];
+
+pub fn is_style_key(key: &str) -> bool {
+    STYLE_KEYS.contains(&key)
+}
+
+/// Check a theme value: colours are normalized to hex, style roles are checked
+/// and kept as written.
+pub fn normalize_value(key: &str, value: &str) -> Result<String> {
+    if is_style_key(key) {
+        style_typst(key, value)?;
+        Ok(value.to_string())
+    } else {
+        colors::normalize_color(value)
+    }
+}
+
+/// The Typst value of style role `key`.
+///
+/// - `bg-gradient`: colours separated by commas, optionally led by an angle
+///   (`135deg`) or `radial`, e.g. `"135deg, #101820, #2b4162"`
+/// - `radius`: a length (`12pt`, `0.5em`); bare numbers are points
+/// - `overlay-opacity`: `0.4` or `40%`
+/// - `weight-heading`, `weight-body`: `bold`, `black`… or 100–900
+/// - `spacing`: factor for the spacing scale, e.g. `1.25`
+/// - `font-heading`, `font-body`, `font-mono`: a font family
+pub fn style_typst(key: &str, value: &str) -> Result<String> {
+    let value = value.trim();
+    let invalid = || anyhow!("Invalid {} '{}'", key, value);
+    match key {
+        "bg-gradient" => {
+            let mut parts = split_top_level(value);
+            let lead = parts
+                .first()
+                .map(|p| p.trim().to_string())
+                .unwrap_or_default();
+            let kind = if lead == "radial" {
+                parts.remove(0);
+                "radial"
+            } else {
+                "linear"
+            };
+            let angle = match lead.strip_suffix("deg").map(|n| n.trim().parse::<f32>()) {
+                Some(Ok(deg)) => {
+                    parts.remove(0);
+                    Some(deg)
+                }
+                Some(Err(_)) => return Err(invalid()),
+                None => None,
+            };
+            if parts.len() < 2 {
+                return Err(anyhow!("Invalid {} '{}' (needs two colours)", key, value));
+            }
+            let stops = parts
+                .iter()
+                .map(|c| Ok(format!("rgb(\"{}\")", colors::normalize_color(c)?)))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(match angle {
+                Some(deg) => format!("gradient.{}({}, angle: {}deg)", kind, stops.join(", "), deg),
+                None => format!("gradient.{}({})", kind, stops.join(", ")),
+            })
+        }
+        "radius" => {
+            let split = value
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(value.len());
+            let (number, unit) = value.split_at(split);
+            number.parse::<f32>().map_err(|_| invalid())?;
+            match unit.trim() {
+                "" => Ok(format!("{}pt", number)),
+                unit @ ("pt" | "mm" | "cm" | "in" | "em" | "%") => {
+                    Ok(format!("{}{}", number, unit))
+                }
+                _ => Err(invalid()),
+            }
+        }
+        "overlay-opacity" => {
+            let opacity = parse_opacity(value).ok_or_else(invalid)?;
+            Ok(format!("{}%", (opacity * 100.0).round()))
+        }
+        "weight-heading" | "weight-body" => match value.parse::<u32>() {
+            Ok(n) if (100..=900).contains(&n) => Ok(n.to_string()),
+            Ok(_) => Err(invalid()),
+            Err(_) if WEIGHTS.contains(&value) => Ok(format!("\"{}\"", value)),
+            Err(_) => Err(anyhow!(
+                "Invalid {} '{}' (expected 100-900 or {})",
+                key,
+                value,
+                WEIGHTS.join(", ")
+            )),
+        },
+        "spacing" => match value.parse::<f32>() {
+            Ok(f) if f > 0.0 => Ok(format!("{}", f)),
+            _ => Err(invalid()),
+        },
+        "font-heading" | "font-body" | "font-mono" if !value.is_empty() => Ok(format!(
+            "\"{}\"",
+            value.replace('\\', "\\\\").replace('"', "\\\"")
+        )),
+        _ => Err(invalid()),
+    }
+}
+
+/// `0.4` or `40%` as a fraction between 0.0 and 1.0.
+fn parse_opacity(value: &str) -> Option<f32> {
+    let opacity = match value.trim().strip_suffix('%') {
+        Some(p) => p.trim().parse::<f32>().ok()? / 100.0,
+        None => value.trim().parse::<f32>().ok()?,
+    };
+    (0.0..=1.0).contains(&opacity).then_some(opacity)
+}
+
+/// The `overlay-opacity` of a theme, if it sets one.
+pub fn overlay_opacity(theme: &HashMap<String, String>) -> Option<f32> {
+    theme.get("overlay-opacity").and_then(|v| parse_opacity(v))
+}
+
+/// Split on commas outside parentheses, so `rgb(1, 2, 3)` stays whole.
+fn split_top_level(value: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut depth = 0;
+    for c in value.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        parts.last_mut().unwrap().push(c);
+    }
+    parts
+        .into_iter()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+/// Check that `theme` defines every role in `KEYS` with a valid colour. With `derive`,
+/// missing roles are filled in from the ones present instead of reported.
+pub fn complete(name: &str, theme: &mut HashMap<String, String>, derive: bool) -> Result<()> {
+    for key in KEYS {
+        if let Some(value) = theme.get(key) {
+            colors::parse_rgb(value)
+                .with_context(|| format!("Theme '{}': invalid '{}'", name, key))?;
+        }
+    }
+    let missing: Vec<&str> = KEYS
+        .iter()
+        .copied()
+        .filter(|k| !theme.contains_key(*k))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    if !derive {
+        return Err(anyhow!(
+            "Theme '{}': missing {} (set derive_missing = true in [defaults] to derive {})",
+            name,
+            missing.join(", "),
+            if missing.len() == 1 { "it" } else { "them" }
+        ));
+    }
+
+    let get = |theme: &HashMap<String, String>, key: &str| {
+        theme
+            .get(key)
+            .and_then(|v| colors::parse_rgb(v).ok())
+            .map(|c| Oklab::from_color(c.into_format::<f32>()))
+    };
+    let hex = |c: Oklab| {
+        let c: Srgb<u8> = Srgb::from_color(c).clamp().into_format();
+        colors::to_hex(c.into())
+    };
+
+    // The neutrals first: bg and text from each other, then the ones between them
+    let (bg, text) = match (get(theme, "bg"), get(theme, "text")) {
+        (Some(bg), Some(text)) => (bg, text),
+        (Some(bg), None) => (bg, opposite(bg)),
+        (None, Some(text)) => (opposite(text), text),
+        (None, None) => {
+            return Err(anyhow!(
+                "Theme '{}': needs at least 'bg' or 'text' to derive the rest",
+                name
+            ))
+        }
+    };
+    // The first accent colour present stands in for the others
+    let lead = ["primary", "accent", "secondary", "highlight"]
+        .iter()
+        .find_map(|k| get(theme, k))
+        .unwrap_or_else(|| text.mix(bg, 0.2));
+
+    for key in missing {
+        let value = match key {
+            "bg" => bg,
+            "text" => text,
+            "surface" => bg.mix(text, 0.08),
+            "muted" => text.mix(bg, 0.45),
+            "primary" => lead,
+            "secondary" => lead.mix(bg, 0.35),
+            "accent" => get(theme, "primary").unwrap_or(lead),
+            _ => get(theme, "accent").unwrap_or(lead),
+        };
+        theme.insert(key.to_string(), hex(value));
+    }
+    Ok(())
+}
+
+/// Black or white, whichever stands out against `c`.
+fn opposite(c: Oklab) -> Oklab {
+    Oklab::new(if c.l > 0.6 { 0.0 } else { 1.0 }, 0.0, 0.0)
+}
+
+/// A role the theme has to define, parsed by the contrast lookup.
+fn role_color(theme: &HashMap<String, String>, key: &str) -> Result<Srgb<f32>> {
+    contrast::role_color(theme, key).unwrap_or_else(|| Err(anyhow!("Theme missing '{}'", key)))
+}
+
+/// CIEDE2000 difference between `a` and `b` for every role in `KEYS`.
+pub fn differences(
+    a: &HashMap<String, String>,
+    b: &HashMap<String, String>,
+) -> Result<Vec<(&'static str, f32)>> {
+    KEYS.iter()
+        .map(|key| {
+            Ok((
+                *key,
+                colors::ciede2000(role_color(a, key)?, role_color(b, key)?),
+            ))
+        })
+        .collect()
+}
+
+// Oklch chroma below which a hue is too unstable to interpolate from
+const ACHROMATIC: f32 = 0.02;
+
+/// `a` and `b` interpolated in Oklch at `t` (0.0 is `a`, 1.0 is `b`). Hues take the
+/// shorter way round; roles where either end is near grey blend in Oklab instead, so
+/// they do not sweep through unrelated hues. Style roles come from the nearer theme.
+pub fn mix(
+    a: &HashMap<String, String>,
+    b: &HashMap<String, String>,
+    t: f32,
+) -> Result<HashMap<String, String>> {
+    let t = t.clamp(0.0, 1.0);
+    let mut theme: HashMap<String, String> = if t < 0.5 { a } else { b }
+        .iter()
+        .filter(|(k, _)| is_style_key(k))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    for key in KEYS {
+        let (from, to) = (role_color(a, key)?, role_color(b, key)?);
+        let (from_lch, to_lch) = (Oklch::from_color(from), Oklch::from_color(to));
+        let mixed = if from_lch.chroma < ACHROMATIC || to_lch.chroma < ACHROMATIC {
+            Srgb::from_color(Oklab::from_color(from).mix(Oklab::from_color(to), t))
+        } else {
+            Srgb::from_color(from_lch.mix(to_lch, t))
+        };
+        let c: Srgb<u8> = mixed.clamp().into_format();
+        theme.insert(key.to_string(), colors::to_hex(c.into()));
+    }
+    Ok(theme)
+}
+
+// Oklch chroma below which a logo colour counts as a neutral rather than a brand colour
+const BRAND_CHROMA: f32 = 0.04;
+// CIEDE2000 difference two brand colours need to fill separate roles
+const BRAND_DISTINCT: f32 = 10.0;
+
+/// Brand colours of a logo from its extracted swatches: the chromatic ones, in order
+/// of share, without near-duplicates. A monochrome logo keeps its main swatch.
+pub fn brand_colors(swatches: &[colors::Swatch]) -> Vec<Srgb<u8>> {
+    let mut brand: Vec<Srgb<u8>> = Vec::new();
+    for s in swatches {
+        let c = s.color.into_format::<f32>();
+        if Oklch::from_color(c).chroma < BRAND_CHROMA {
+            continue;
+        }
+        if brand
+            .iter()
+            .all(|b| colors::ciede2000(b.into_format(), c) >= BRAND_DISTINCT)
+        {
+            brand.push(s.color);
+        }
+    }
+    if brand.is_empty() {
+        brand.extend(swatches.first().map(|s| s.color));
+    }
+    brand
+}
+
+/// A dark or light theme around `brand` (most prominent first). The neutrals are
+/// tinted with the first brand hue; roles without a brand colour of their own are
+/// rotated from it. Every contrast pairing is corrected, and a theme that still
+/// fails one is an error.
+pub fn from_brand(brand: &[Srgb<u8>], dark: bool) -> Result<HashMap<String, String>> {
+    let lch: Vec<Oklch> = brand
+        .iter()
+        .map(|c| Oklch::from_color(c.into_format::<f32>()))
+        .collect();
+    let lead = *lch.first().ok_or_else(|| anyhow!("No brand colours"))?;
+    let hue = lead.hue;
+    let rotated = |degrees: f32| Oklch::new(lead.l, lead.chroma.max(0.08), hue + degrees);
+
+    // Lightness and chroma of bg, surface, muted and text
+    let neutrals = if dark {
+        [(0.18, 0.02), (0.24, 0.025), (0.72, 0.02), (0.96, 0.01)]
+    } else {
+        [(0.98, 0.008), (0.94, 0.015), (0.50, 0.02), (0.22, 0.02)]
+    };
+    let primary = lead;
+    let secondary = lch.get(1).copied().unwrap_or_else(|| rotated(-40.0));
+    let accent = lch.get(2).copied().unwrap_or_else(|| rotated(180.0));
+    let highlight = lch.get(3).copied().unwrap_or_else(|| {
+        Oklch::new(
+            if dark { 0.85 } else { 0.6 },
+            lead.chroma.max(0.1),
+            hue + 30.0,
+        )
+    });
+
+    let hex = |c: Oklch| {
+        let c: Srgb<u8> = Srgb::from_color(c).clamp().into_format();
+        colors::to_hex(c.into())
+    };
+    let mut theme = HashMap::new();
+    for (key, (l, chroma)) in ["bg", "surface", "muted", "text"].iter().zip(neutrals) {
+        theme.insert(key.to_string(), hex(Oklch::new(l, chroma, hue)));
+    }
+    for (key, c) in [
+        ("primary", primary),
+        ("secondary", secondary),
+        ("accent", accent),
+        ("highlight", highlight),
+    ] {
+        theme.insert(key.to_string(), hex(c));
+    }
+
+    contrast::correct(&mut theme)?;
+    let failing: Vec<String> = contrast::audit(&theme)?
+        .into_iter()
+        .filter(|f| !f.passes())
+        .map(|f| f.to_string())
+        .collect();
+    if !failing.is_empty() {
+        return Err(anyhow!(
+            "Could not reach contrast targets:\n  {}",
+            failing.join("\n  ")
+        ));
+    }
+    Ok(theme)
+}
+
 pub mod predefined {
+    use super::map_base16;
+    use anyhow::{anyhow, Result};
     use std::collections::HashMap;
+    use std::path::Path;
 
+    use crate::constants;
+
+    /// Every selectable theme, in layers: the built-ins, then the files in the
+    /// themes directory, then `[themes]` from the config. A later layer replaces
+    /// a theme of the same name; `extends = "<name>"` inherits a theme's keys instead.
     pub fn get_theme_palettes(
         config: Option<&crate::config::Config>,
     ) -> HashMap<String, HashMap<String, String>> {
-        if let Some(cfg) = config {
-            if let Some(themes) = &cfg.themes {
-                return themes.clone();
+        // `Config::load` already rejected broken config themes
+        layered(config).unwrap_or_else(|e| {
+            eprintln!("⚠ {:#}", e);
+            layered(None).unwrap_or_default()
+        })
+    }
+
+    /// Check the `extends` chains and the roles of the config themes.
+    pub fn validate(config: &crate::config::Config) -> Result<()> {
+        layered(Some(config)).map(|_| ())
+    }
+
+    /// Which layer theme `name` comes from: `posts.toml`, `themes/` or `built-in`.
+    pub fn origin(config: Option<&crate::config::Config>, name: &str) -> &'static str {
+        let in_config = config
+            .and_then(|c| c.themes.as_ref())
+            .is_some_and(|t| t.contains_key(name));
+        let dir = Path::new(themes_dir(config));
+        let in_files = ["yaml", "yml", "json", "toml"]
+            .iter()
+            .any(|ext| dir.join(format!("{}.{}", name, ext)).is_file());
+        if in_config {
+            "posts.toml"
+        } else if in_files {
+            "themes/"
+        } else {
+            "built-in"
+        }
+    }
+
+    fn themes_dir(config: Option<&crate::config::Config>) -> &str {
+        config
+            .and_then(|c| c.defaults.get("themes_dir"))
+            .and_then(|v| v.as_str())
+            .unwrap_or(constants::THEMES_DIR)
+    }
+
+    fn layered(
+        config: Option<&crate::config::Config>,
+    ) -> Result<HashMap<String, HashMap<String, String>>> {
+        let dir = themes_dir(config);
+        let derive = config
+            .and_then(|c| c.defaults.get("derive_missing"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        // Theme files, by file stem
+        let mut m = builtin();
+        m.extend(super::files::load_dir(Path::new(dir), derive));
+
+        if let Some(themes) = config.and_then(|c| c.themes.as_ref()) {
+            let mut resolved = HashMap::new();
+            for name in themes.keys() {
+                let mut theme = resolve(name, themes, &m, &mut Vec::new())?;
+                super::complete(name, &mut theme, derive)?;
+                resolved.insert(name.clone(), theme);
             }
+            m.extend(resolved);
         }
+        Ok(m)
+    }
 
+    /// A config theme with its `extends` chain applied. Parents are looked up among
+    /// the other config themes first, then in the layers below; a theme extending
+    /// its own name tweaks the theme it replaces.
+    fn resolve(
+        name: &str,
+        themes: &HashMap<String, HashMap<String, String>>,
+        below: &HashMap<String, HashMap<String, String>>,
+        chain: &mut Vec<String>,
+    ) -> Result<HashMap<String, String>> {
+        let own = &themes[name];
+        chain.push(name.to_string());
+        let mut theme = match own.get("extends") {
+            Some(parent) if parent != name && chain.contains(parent) => {
+                return Err(anyhow!(
+                    "Theme '{}': circular extends ({} → {})",
+                    name,
+                    chain.join(" → "),
+                    parent
+                ));
+            }
+            Some(parent) if parent != name && themes.contains_key(parent) => {
+                resolve(parent, themes, below, chain)?
+            }
+            Some(parent) => below
+                .get(parent)
+                .cloned()
+                .ok_or_else(|| anyhow!("Theme '{}': extends unknown theme '{}'", name, parent))?,
+            None => HashMap::new(),
+        };
+        chain.pop();
+
+        theme.extend(
+            own.iter()
+                .filter(|(k, _)| k.as_str() != "extends")
+                .map(|(k, v)| (k.clone(), v.clone())),
+        );
+        Ok(theme)
+    }
+
+    fn builtin() -> HashMap<String, HashMap<String, String>> {
         let mut m = HashMap::new();
 
         // Fallback: Dark
@@ -74,23 +590,10 @@ pub mod predefined {
 
         // --- NEW BASE16 THEMES ---
 
-        let map_base16 = |colors: [&str; 16]| {
-            let mut theme = HashMap::new();
-            theme.insert("bg".to_string(), colors[0].to_string());
-            theme.insert("surface".to_string(), colors[1].to_string());
-            theme.insert("muted".to_string(), colors[3].to_string());
-            theme.insert("text".to_string(), colors[5].to_string());
-            theme.insert("primary".to_string(), colors[8].to_string());
-            theme.insert("highlight".to_string(), colors[9].to_string());
-            theme.insert("accent".to_string(), colors[12].to_string()); // base0C/D
-            theme.insert("secondary".to_string(), colors[14].to_string()); // base0E
-            theme
-        };
-
         // Nord
         m.insert(
             "nord".to_string(),
-            map_base16([
+            map_base16(&[
                 "#2e3440", "#3b4252", "#434c5e", "#4c566a", "#d8dee9", "#e5e9f0", "#eceff4",
                 "#8fbcbb", "#bf616a", "#d08770", "#ebcb8b", "#a3be8c", "#88c0d0", "#81a1c1",
                 "#b48ead", "#5e81ac",
@@ -100,7 +603,7 @@ pub mod predefined {
         // Gruvbox Dark
         m.insert(
             "gruvbox-dark".to_string(),
-            map_base16([
+            map_base16(&[
                 "#282828", "#3c3836", "#504945", "#665c54", "#bdae93", "#d5c4a1", "#ebdbb2",
                 "#fbf1c7", "#fb4934", "#fe8019", "#fabd2f", "#b8bb26", "#8ec07c", "#83a598",
                 "#d3869b", "#fe8019",
@@ -110,7 +613,7 @@ pub mod predefined {
         // Catppuccin Mocha
         m.insert(
             "catppuccin-mocha".to_string(),
-            map_base16([
+            map_base16(&[
                 "#1e1e2e", "#181825", "#313244", "#45475a", "#585b70", "#cdd6f4", "#f5e0dc",
                 "#b4befe", "#f38ba8", "#fab387", "#f9e2af", "#a6e3a1", "#94e2d5", "#89b4fa",
                 "#cba6f7", "#f2cdcd",
@@ -120,7 +623,7 @@ pub mod predefined {
         // Tokyo Night
         m.insert(
             "tokyo-night".to_string(),
-            map_base16([
+            map_base16(&[
                 "#1a1b26", "#16161e", "#24283b", "#414868", "#565f89", "#cfc9c2", "#a9b1d6",
                 "#c0caf5", "#f7768e", "#ff9e64", "#e0af68", "#9ece6a", "#73daca", "#7aa2f7",
                 "#bb9af7", "#2ac3de",
@@ -130,7 +633,7 @@ pub mod predefined {
         // Dracula
         m.insert(
             "dracula".to_string(),
-            map_base16([
+            map_base16(&[
                 "#282a36", "#44475a", "#44475a", "#6272a4", "#6272a4", "#f8f8f2", "#f8f8f2",
                 "#ffffff", "#ff5555", "#ffb86c", "#f1fa8c", "#50fa7b", "#8be9fd", "#bd93f9",
                 "#ff79c6", "#bd93f9",
@@ -140,7 +643,7 @@ pub mod predefined {
         // One Dark
         m.insert(
             "one-dark".to_string(),
-            map_base16([
+            map_base16(&[
                 "#282c34", "#353b45", "#3e4451", "#545862", "#565c64", "#abb2bf", "#b6bdca",
                 "#c8ccd4", "#e06c75", "#d19a66", "#e5c07b", "#98c379", "#56b6c2", "#61afef",
                 "#c678dd", "#be5046",
@@ -150,7 +653,7 @@ pub mod predefined {
         // Rose Pine
         m.insert(
             "rose-pine".to_string(),
-            map_base16([
+            map_base16(&[
                 "#191724", "#1f1d2e", "#26233a", "#6e6a86", "#908caa", "#e0def4", "#e0def4",
                 "#524f67", "#eb6f92", "#f6c177", "#ebbcba", "#31748f", "#9ccfd8", "#c4a7e7",
                 "#f6c177", "#524f67",
@@ -160,7 +663,7 @@ pub mod predefined {
         // Catppuccin Latte (Light)
         m.insert(
             "catppuccin-latte".to_string(),
-            map_base16([
+            map_base16(&[
                 "#eff1f5", "#e6e9ef", "#ccd0da", "#bcc0cc", "#acb0be", "#4c4f69", "#5c5f77",
                 "#6c6f85", "#d20f39", "#fe640b", "#df8e1d", "#40a02b", "#179299", "#1e66f5",
                 "#8839ef", "#ea76cb",
@@ -170,7 +673,7 @@ pub mod predefined {
         // Gruvbox Light
         m.insert(
             "gruvbox-light".to_string(),
-            map_base16([
+            map_base16(&[
                 "#fbf1c7", "#ebdbb2", "#d5c4a1", "#bdae93", "#665c54", "#3c3836", "#282828",
                 "#1d2021", "#9d0006", "#af3a03", "#b57614", "#79740e", "#427b58", "#076678",
                 "#8f3f71", "#af3a03",
@@ -180,7 +683,7 @@ pub mod predefined {
         // Solarized Dark
         m.insert(
             "solarized-dark".to_string(),
-            map_base16([
+            map_base16(&[
                 "#002b36", "#073642", "#586e75", "#657b83", "#839496", "#93a1a1", "#eee8d5",
                 "#fdf6e3", "#dc322f", "#cb4b16", "#b58900", "#859900", "#2aa198", "#268bd2",
                 "#6c71c4", "#d33682",
@@ -190,7 +693,7 @@ pub mod predefined {
         // Solarized Light
         m.insert(
             "solarized-light".to_string(),
-            map_base16([
+            map_base16(&[
                 "#fdf6e3", "#eee8d5", "#93a1a1", "#839496", "#657b83", "#586e75", "#073642",
                 "#002b36", "#dc322f", "#cb4b16", "#b58900", "#859900", "#2aa198", "#268bd2",
                 "#6c71c4", "#d33682",
@@ -199,4 +702,505 @@ pub mod predefined {
 
         m
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn themes(entries: &[(&str, &[(&str, &str)])]) -> HashMap<String, HashMap<String, String>> {
+            entries
+                .iter()
+                .map(|(name, keys)| {
+                    let keys = keys
+                        .iter()
+                        .map(|(k, v)| (k.to_string(), v.to_string()))
+                        .collect();
+                    (name.to_string(), keys)
+                })
+                .collect()
+        }
+
+        fn config(toml: &str) -> crate::config::Config {
+            toml::from_str(&format!("[defaults]\n{}", toml)).unwrap()
+        }
+
+        #[test]
+        fn extends_follows_a_multi_level_chain() {
+            let declared = themes(&[
+                ("brand", &[("extends", "dark"), ("primary", "#111111")]),
+                ("campaign", &[("extends", "brand"), ("accent", "#222222")]),
+                ("launch", &[("extends", "campaign"), ("text", "#333333")]),
+            ]);
+            let theme = resolve("launch", &declared, &builtin(), &mut Vec::new()).unwrap();
+
+            assert_eq!(theme["bg"], builtin()["dark"]["bg"]);
+            assert_eq!(theme["primary"], "#111111");
+            assert_eq!(theme["accent"], "#222222");
+            assert_eq!(theme["text"], "#333333");
+            assert!(!theme.contains_key("extends"));
+        }
+
+        #[test]
+        fn extends_lets_the_child_override_keys() {
+            let declared = themes(&[
+                ("base", &[("extends", "light"), ("primary", "#111111")]),
+                ("child", &[("extends", "base"), ("primary", "#999999")]),
+                // Extending its own name tweaks the built-in theme it replaces
+                ("dark", &[("extends", "dark"), ("accent", "#abcdef")]),
+            ]);
+            let below = builtin();
+
+            let child = resolve("child", &declared, &below, &mut Vec::new()).unwrap();
+            assert_eq!(child["primary"], "#999999");
+            let base = resolve("base", &declared, &below, &mut Vec::new()).unwrap();
+            assert_eq!(base["primary"], "#111111");
+
+            let dark = resolve("dark", &declared, &below, &mut Vec::new()).unwrap();
+            assert_eq!(dark["accent"], "#abcdef");
+            assert_eq!(dark["bg"], below["dark"]["bg"]);
+        }
+
+        #[test]
+        fn extends_rejects_unknown_parents_and_cycles() {
+            let declared = themes(&[
+                ("orphan", &[("extends", "nowhere")]),
+                ("a", &[("extends", "b")]),
+                ("b", &[("extends", "c")]),
+                ("c", &[("extends", "a")]),
+            ]);
+            let err = resolve("orphan", &declared, &builtin(), &mut Vec::new()).unwrap_err();
+            assert!(
+                err.to_string().contains("extends unknown theme 'nowhere'"),
+                "{}",
+                err
+            );
+            let err = resolve("a", &declared, &builtin(), &mut Vec::new()).unwrap_err();
+            assert!(
+                err.to_string().contains("circular extends (a → b → c → a)"),
+                "{}",
+                err
+            );
+
+            assert!(validate(&config("[themes.x]\nextends = \"nowhere\"\n")).is_err());
+            assert!(validate(&config(
+                "[themes.x]\nextends = \"y\"\n[themes.y]\nextends = \"x\"\n"
+            ))
+            .is_err());
+            assert!(validate(&config(
+                "[themes.x]\nextends = \"dark\"\nprimary = \"#123456\"\n"
+            ))
+            .is_ok());
+        }
+    }
+}
+
+/// Themes loaded from files: base16/base24 YAML schemes, or JSON and TOML with
+/// either `base00`… slots or the theme roles themselves.
+pub mod files {
+    use anyhow::{anyhow, Context, Result};
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Mutex, OnceLock};
+
+    use super::map_base16;
+
+    type Themes = HashMap<String, HashMap<String, String>>;
+
+    // Directories already read, with their `derive` flag: every theme lookup goes
+    // through `load_dir`, but each file is parsed and reported on once per run
+    static LOADED: OnceLock<Mutex<HashMap<(PathBuf, bool), Themes>>> = OnceLock::new();
+
+    /// Every theme file in `dir`, keyed by file stem. Files that fail to load or
+    /// lack roles (unless `derive`) are reported and skipped; a missing directory
+    /// yields no themes.
+    pub fn load_dir(dir: &Path, derive: bool) -> Themes {
+        let cache = LOADED.get_or_init(Default::default);
+        let key = (dir.to_path_buf(), derive);
+        if let Some(themes) = cache.lock().ok().and_then(|c| c.get(&key).cloned()) {
+            return themes;
+        }
+        let themes = read_dir(dir, derive);
+        if let Ok(mut c) = cache.lock() {
+            c.insert(key, themes.clone());
+        }
+        themes
+    }
+
+    fn read_dir(dir: &Path, derive: bool) -> Themes {
+        let mut themes = HashMap::new();
+        let entries = match fs::read_dir(dir) {
+            Ok(e) => e,
+            Err(_) => return themes,
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let supported = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| matches!(e, "yaml" | "yml" | "json" | "toml"));
+            let stem = path.file_stem().and_then(|s| s.to_str());
+            let (Some(stem), true) = (stem, supported) else {
+                continue;
+            };
+            let loaded = load_file(&path).and_then(|mut theme| {
+                super::complete(stem, &mut theme, derive)?;
+                Ok(theme)
+            });
+            match loaded {
+                Ok(theme) => {
+                    themes.insert(stem.to_string(), theme);
+                }
+                Err(e) => eprintln!("⚠ Theme file {}: {:#}", path.display(), e),
+            }
+        }
+        themes
+    }
+
+    /// Load one theme file, picking the parser from its extension.
+    pub fn load_file(path: &Path) -> Result<HashMap<String, String>> {
+        let content = fs::read_to_string(path).context("Failed to read theme file")?;
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        let entries = match ext {
+            "yaml" | "yml" => yaml_entries(&content),
+            "json" => {
+                let value: serde_json::Value =
+                    serde_json::from_str(&content).context("Failed to parse JSON")?;
+                let mut out = HashMap::new();
+                flatten_json(&value, &mut out);
+                out
+            }
+            "toml" => {
+                let table: toml::Table =
+                    toml::from_str(&content).context("Failed to parse TOML")?;
+                let mut out = HashMap::new();
+                flatten_toml(&table, &mut out);
+                out
+            }
+            other => return Err(anyhow!("Unsupported theme format '{}'", other)),
+        };
+        from_entries(entries)
+    }
+
+    /// Whether a theme file key is a `base00`–`base17` slot.
+    fn is_slot(key: &str) -> bool {
+        key.len() == 6
+            && key.get(..4).is_some_and(|b| b.eq_ignore_ascii_case("base"))
+            && key
+                .get(4..)
+                .and_then(|n| u8::from_str_radix(n, 16).ok())
+                .is_some_and(|n| n < 0x18)
+    }
+
+    /// Whether a theme file key is one the themes use: a slot, a colour or a style role.
+    /// Anything else (name, author, variant…) describes the scheme and is skipped.
+    fn is_theme_key(key: &str) -> bool {
+        is_slot(key) || super::KEYS.contains(&key) || super::is_style_key(key)
+    }
+
+    /// Turn the flat key/value pairs of a theme file into theme roles.
+    fn from_entries(entries: HashMap<String, String>) -> Result<HashMap<String, String>> {
+        let slots: Vec<String> = (0..16).map(|i| format!("base{:02X}", i)).collect();
+        let get = |key: &str| {
+            entries
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v.as_str())
+        };
+
+        let theme = if get("base00").is_some() {
+            let mut colors = Vec::new();
+            for slot in &slots {
+                colors.push(get(slot).ok_or_else(|| anyhow!("Missing '{}'", slot))?);
+            }
+            map_base16(&colors)
+        } else {
+            entries
+                .iter()
+                .filter(|(k, _)| is_theme_key(k) && !is_slot(k))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect()
+        };
+        if theme.is_empty() {
+            return Err(anyhow!("No colors found"));
+        }
+
+        theme
+            .into_iter()
+            .map(|(key, value)| {
+                let value = super::normalize_value(&key, &value)
+                    .with_context(|| format!("Invalid '{}'", key))?;
+                Ok((key, value))
+            })
+            .collect()
+    }
+
+    /// The slot and role pairs of a base16/base24 scheme. Only those keys are read,
+    /// at any depth, so the flat layout and the tinted-theming one with a `palette:`
+    /// block both work and their metadata cannot clash with them.
+    fn yaml_entries(content: &str) -> HashMap<String, String> {
+        let mut out = HashMap::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().trim_matches(|c| c == '"' || c == '\'');
+            if !is_theme_key(key) {
+                continue;
+            }
+            let value = value.trim();
+            let value = match value.chars().next() {
+                Some(q @ ('"' | '\'')) => value[1..].split(q).next().unwrap_or(""),
+                // Comments need a space before the `#`, so `#2e3440` is a value
+                _ => value.split(" #").next().unwrap_or("").trim(),
+            };
+            if !value.is_empty() {
+                out.insert(key.to_string(), value.to_string());
+            }
+        }
+        out
+    }
+
+    fn flatten_json(value: &serde_json::Value, out: &mut HashMap<String, String>) {
+        if let serde_json::Value::Object(map) = value {
+            for (key, value) in map {
+                match value {
+                    serde_json::Value::String(s) => {
+                        out.insert(key.clone(), s.clone());
+                    }
+                    // Style roles such as `spacing` may be plain numbers
+                    serde_json::Value::Number(n) => {
+                        out.insert(key.clone(), n.to_string());
+                    }
+                    serde_json::Value::Object(_) => flatten_json(value, out),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn flatten_toml(table: &toml::Table, out: &mut HashMap<String, String>) {
+        for (key, value) in table {
+            match value {
+                toml::Value::String(s) => {
+                    out.insert(key.clone(), s.clone());
+                }
+                toml::Value::Float(f) => {
+                    out.insert(key.clone(), f.to_string());
+                }
+                toml::Value::Integer(i) => {
+                    out.insert(key.clone(), i.to_string());
+                }
+                toml::Value::Table(t) => flatten_toml(t, out),
+                _ => {}
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn yaml_reads_flat_and_nested_schemes() {
+            let flat = "scheme: \"Nord\"
+author: \"arcticicestudio\"
+base00: \"2E3440\" # bg
+base0A: '#ebcb8b'
+";
+            let entries = yaml_entries(flat);
+            assert_eq!(entries.len(), 2);
+            assert_eq!(entries["base00"], "2E3440");
+            assert_eq!(entries["base0A"], "#ebcb8b");
+
+            let nested = "system: \"base24\"
+name: \"Nord\"
+variant: \"dark\"
+palette:
+  base00: \"#2e3440\"
+  base17: \"#b48ead\"
+";
+            let entries = yaml_entries(nested);
+            assert_eq!(entries.len(), 2);
+            assert_eq!(entries["base00"], "#2e3440");
+            assert_eq!(entries["base17"], "#b48ead");
+        }
+
+        #[test]
+        fn yaml_reads_roles_and_skips_other_keys() {
+            let entries =
+                yaml_entries("bg: \"#101010\"\nradius: 8pt\ncomment: hello\n# text: \"#ffffff\"\n");
+            assert_eq!(entries.len(), 2);
+            assert_eq!(entries["bg"], "#101010");
+            assert_eq!(entries["radius"], "8pt");
+        }
+
+        #[test]
+        fn yaml_handles_quotes_comments_and_bare_hex() {
+            let content = "# Base16 scheme, with comments
+scheme: \"Quoted: with colon\" # not a slot
+base00: \"1d1f21\"   # double quotes
+base01: '282a36'     # single quotes
+base02: 373b41 # bare value, trailing comment
+base03: #969896
+  # base04: \"ffffff\" indented comment
+\"base0A\": \"f0c674\"
+'base0b': 'b5bd68' # after the quotes
+BASE0C: 8abeb7
+";
+            let entries = yaml_entries(content);
+            assert_eq!(entries.len(), 7, "{:?}", entries);
+            assert_eq!(entries["base00"], "1d1f21");
+            assert_eq!(entries["base01"], "282a36");
+            assert_eq!(entries["base02"], "373b41");
+            assert_eq!(entries["base03"], "#969896");
+            assert!(!entries.contains_key("base04"));
+            assert_eq!(entries["base0A"], "f0c674");
+            assert_eq!(entries["base0b"], "b5bd68");
+            assert_eq!(entries["BASE0C"], "8abeb7");
+        }
+
+        #[test]
+        fn bare_hex_slots_become_a_theme() {
+            // Slots without `#` and with any letter case, as base16 schemes write them
+            let content: String = (0..16)
+                .map(|i| {
+                    let key = if i % 2 == 0 {
+                        format!("base{:02X}", i)
+                    } else {
+                        format!("base{:02x}", i)
+                    };
+                    format!("{}: \"{:02x}{:02x}{:02x}\"\n", key, i * 16, i * 16, i * 16)
+                })
+                .collect();
+            let theme = from_entries(yaml_entries(&content)).unwrap();
+            assert_eq!(theme["bg"], "#000000");
+            assert!(theme.values().all(|v| v.starts_with('#') && v.len() == 7));
+        }
+
+        #[test]
+        fn slots_are_recognized() {
+            for (key, expected) in [
+                ("base00", true),
+                ("BASE0F", true),
+                ("base17", true),
+                ("base18", false),
+                ("base0", false),
+                ("basexx", false),
+            ] {
+                assert_eq!(is_slot(key), expected, "{}", key);
+            }
+        }
+
+        #[test]
+        fn json_and_toml_flatten_nested_tables() {
+            let value: serde_json::Value = serde_json::from_str(
+                r##"{"name": "x", "colors": {"bg": "#000000", "spacing": 1.5}}"##,
+            )
+            .unwrap();
+            let mut json = HashMap::new();
+            flatten_json(&value, &mut json);
+            assert_eq!(json["bg"], "#000000");
+            assert_eq!(json["spacing"], "1.5");
+            assert_eq!(json["name"], "x");
+
+            let table: toml::Table =
+                toml::from_str("[palette]\nbase00 = \"#000000\"\nweight = 2\n").unwrap();
+            let mut toml = HashMap::new();
+            flatten_toml(&table, &mut toml);
+            assert_eq!(toml["base00"], "#000000");
+            assert_eq!(toml["weight"], "2");
+        }
+
+        #[test]
+        fn entries_keep_only_theme_roles() {
+            let entries = HashMap::from([
+                ("name".to_string(), "Mine".to_string()),
+                ("bg".to_string(), "rgb(0, 0, 0)".to_string()),
+                ("text".to_string(), "#fff".to_string()),
+            ]);
+            let theme = from_entries(entries).unwrap();
+            assert_eq!(theme.len(), 2);
+            assert_eq!(theme["bg"], "#000000");
+            assert_eq!(theme["text"], "#ffffff");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_top_level_keeps_functions_whole() {
+        assert_eq!(
+            split_top_level("135deg, rgb(1, 2, 3), #fff ,"),
+            ["135deg", "rgb(1, 2, 3)", "#fff"]
+        );
+        assert_eq!(
+            split_top_level("oklch(0.5 0.1 200)"),
+            ["oklch(0.5 0.1 200)"]
+        );
+        assert!(split_top_level(" , ").is_empty());
+    }
+
+    #[test]
+    fn style_values_become_typst() {
+        for (key, value, expected) in [
+            (
+                "bg-gradient",
+                "#000, #fff",
+                "gradient.linear(rgb(\"#000000\"), rgb(\"#ffffff\"))",
+            ),
+            (
+                "bg-gradient",
+                "135deg, #000, rgb(255, 0, 0)",
+                "gradient.linear(rgb(\"#000000\"), rgb(\"#ff0000\"), angle: 135deg)",
+            ),
+            (
+                "bg-gradient",
+                "radial, #000, #fff",
+                "gradient.radial(rgb(\"#000000\"), rgb(\"#ffffff\"))",
+            ),
+            ("radius", "12", "12pt"),
+            ("radius", "0.5em", "0.5em"),
+            ("overlay-opacity", "40%", "40%"),
+            ("overlay-opacity", "0.25", "25%"),
+            ("weight-heading", "700", "700"),
+            ("weight-body", "medium", "\"medium\""),
+            ("spacing", "1.25", "1.25"),
+            ("font-heading", "Inter", "\"Inter\""),
+            ("font-body", "My \"Font\"", "\"My \\\"Font\\\"\""),
+        ] {
+            assert_eq!(
+                style_typst(key, value).unwrap(),
+                expected,
+                "{} = {}",
+                key,
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_style_values_are_rejected() {
+        for (key, value) in [
+            ("bg-gradient", "#000"),
+            ("bg-gradient", "fastdeg, #000, #fff"),
+            ("radius", "12px"),
+            ("radius", "big"),
+            ("overlay-opacity", "150%"),
+            ("weight-heading", "950"),
+            ("weight-body", "heavy"),
+            ("spacing", "0"),
+            ("font-mono", ""),
+            ("unknown", "1"),
+        ] {
+            assert!(style_typst(key, value).is_err(), "{} = {}", key, value);
+        }
+    }
 }
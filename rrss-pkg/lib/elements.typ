@@ -12,12 +12,13 @@
 /// -> content
 #let badge(t, label, bg: none) = {
   let fill-color = if bg != none { bg } else { t.primary }
+  let sp = resolve-spacing(t)
   box(
-    inset: (x: spacing.sm, y: spacing.xs),
-    radius: 4pt,
+    inset: (x: sp.sm, y: sp.xs),
+    radius: corner-radius(t),
     fill: fill-color,
   )[
-    #set text(size: sizes.small, weight: "bold", fill: white, font: fonts.heading.first())
+    #set text(size: sizes.small, weight: "bold", fill: white, font: resolve-fonts(t).heading.first())
     #upper(label)
   ]
 }
@@ -37,12 +38,13 @@
 /// - text-content (str): Texto de la marca (e.g. "@usuario")
 /// -> content
 #let watermark(t, text-content) = {
+  let sp = resolve-spacing(t)
   place(
     bottom + right,
-    dx: -spacing.md,
-    dy: -spacing.md,
+    dx: -sp.md,
+    dy: -sp.md,
   )[
-    #set text(size: sizes.caption, fill: t.muted.transparentize(40%), font: fonts.body.first())
+    #set text(size: sizes.caption, fill: t.muted.transparentize(40%), font: resolve-fonts(t).body.first())
     #text-content
   ]
 }
@@ -83,9 +85,9 @@
 /// - items (array): Lista de strings
 /// -> content
 #let styled-list(t, items) = {
-  set text(fill: t.text, font: fonts.body.first(), size: sizes.body)
+  set text(fill: t.text, font: resolve-fonts(t).body.first(), size: sizes.body)
   for item in items {
-    block(spacing: spacing.sm)[
+    block(spacing: resolve-spacing(t).sm)[
       #text(fill: t.primary, weight: "bold")[▸ ]
       #item
     ]
@@ -99,10 +101,11 @@
 /// - unit (str): Unidad (%, M, K, etc.)
 /// -> content
 #let stat-number(t, number, unit: "") = {
+  let f = resolve-fonts(t)
   [
-    #text(size: sizes.stat-xl, weight: "black", fill: t.primary, font: fonts.heading.first())[#number]
+    #text(size: sizes.stat-xl, weight: weight(t, "heading", "black"), fill: t.primary, font: f.heading.first())[#number]
     #if unit != "" {
-      text(size: sizes.title, weight: "bold", fill: t.primary, font: fonts.heading.first())[#unit]
+      text(size: sizes.title, weight: "bold", fill: t.primary, font: f.heading.first())[#unit]
     }
   ]
 }
@@ -115,9 +118,9 @@
 #let footer-handle(t, handle) = {
   place(
     bottom + center,
-    dy: -spacing.md,
+    dy: -resolve-spacing(t).md,
   )[
-    #set text(size: sizes.caption, fill: t.muted, font: fonts.body.first(), weight: "medium")
+    #set text(size: sizes.caption, fill: t.muted, font: resolve-fonts(t).body.first(), weight: "medium")
     #handle
   ]
 }
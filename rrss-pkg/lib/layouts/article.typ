@@ -18,14 +18,14 @@
 /// -> content
 #let article(
   t,
-  brand: "PRESUPOSICIONALISMO",
+  brand: "",
   logo: none,
   title: "",
   quote-text: "",
   bg-image: none,
   overlay: none,
   accent: none,
-  url: "Presuposicionalismo.com",
+  url: "",
   ..args,
 ) = {
   // Override accent color if provided
@@ -65,7 +65,7 @@
 
   // --- SECCIÓN BODY (BACKGROUND + CONTENIDO) ---
   place(top + left, dy: body-start)[
-    #block(width: 100%, height: body-height, clip: true, fill: t.bg)[
+    #block(width: 100%, height: body-height, clip: true, fill: bg-fill(t))[
       #let f = resolve-fonts(t)
       // Capa 1: Imagen de Fondo
       #if bg-image != none {
@@ -74,10 +74,11 @@
         ]
       }
 
-      // Capa 2: Overlay
+      // Capa 2: Overlay (y la capa `overlay-opacity` del tema)
       #if overlay != none {
         place(center + horizon)[#overlay]
       }
+      #place(center + horizon)[#scrim(t)]
 
       // Capa 3: Contenido de Texto
       #place(center + horizon)[
@@ -87,14 +88,14 @@
 
           // Título Principal
           #set par(leading: 0.8em)
-          #text(size: sizes.title, weight: "black")[#upper(title)]
+          #text(size: sizes.title, weight: weight(t, "heading", "black"))[#upper(title)]
 
           #v(1em)
           #line(length: 30%, stroke: 2pt + t.accent)
           #v(1em)
 
           // Cita / Texto
-          #set text(font: f.body.first(), style: "italic", size: sizes.body)
+          #set text(font: f.body.first(), style: "italic", size: sizes.body, weight: weight(t, "body", "regular"))
           "#quote-text"
         ]
       ]
@@ -6,8 +6,15 @@
 
 /// Layout de carrusel — múltiples slides con fondo continuo.
 ///
+/// Cada slide es un string/content o un diccionario con:
+/// - body (str|content): Texto de la slide.
+/// - t (dictionary): Tema propio de la slide (por defecto `t`).
+/// - bg-image (content): Imagen propia, en lugar del tramo del fondo continuo.
+/// - layout (str): "cover", "content", "quote" o "image" (por defecto "cover"
+///   para la primera slide si hay título y "content" para el resto).
+///
 /// - t (dictionary): Paleta de tema
-/// - slides (array): Lista de contenidos. Puede ser string o content.
+/// - slides (array): Lista de slides.
 /// - title (str): Título principal (para la slide de portada).
 /// - bg-image (content): Imagen de fondo (SVG/PNG) que se extenderá por todo el carrusel.
 /// - brand (str): Marca para el header.
@@ -18,110 +25,162 @@
   slides: (),
   title: "",
   bg-image: none,
-  brand: "Presuposicionalismo",
+  brand: "",
   ..args,
 ) = {
   let dims = platforms.at(platform)
   let total = slides.len()
 
   // Iterar sobre slides
-  for (i, slide) in slides.enumerate() {
+  for (i, entry) in slides.enumerate() {
+    let slide = if type(entry) == dictionary { entry } else { (body: entry) }
+    let body = slide.at("body", default: "")
+    let st = slide.at("t", default: t)
+    let slide-image = slide.at("bg-image", default: none)
+    let kind = slide.at("layout", default: if i == 0 and title != "" { "cover" } else { "content" })
+    let sp = resolve-spacing(st)
+    let f = resolve-fonts(st)
+
     // Salto de página entre slides
     if i > 0 { pagebreak(weak: true) }
 
-    // Fondo base
+    // Fondo base: una slide con tema propio cubre el de la página
+    if st != t {
+      place(top + left, block(width: 100%, height: 100%, fill: bg-fill(st)))
+    }
 
-    // ─── Fondo Continuo (Sliding Window) ──────────────────────────
-    // La imagen se escala al ancho total del carrusel y se desplaza
-    if bg-image != none {
+    // ─── Fondo ────────────────────────────────────────────────────
+    // Imagen propia de la slide, o el tramo que le toca del fondo continuo:
+    // la imagen se escala al ancho total del carrusel y se desplaza
+    if slide-image != none or bg-image != none {
       place(top + left)[
         #block(width: 100%, height: 100%, clip: true)[
-          #place(top + left, dx: -100% * i)[
-            #block(width: 100% * total, height: 100%)[
-              #set image(fit: "cover")
-              #bg-image
+          #if slide-image != none {
+            place(top + left, slide-image)
+          } else {
+            place(top + left, dx: -100% * i)[
+              #block(width: 100% * total, height: 100%)[
+                #set image(fit: "cover")
+                #bg-image
+              ]
             ]
-          ]
-          // Capa de oscurecimiento para legibilidad
-          #block(width: 100%, height: 100%, fill: t.bg.transparentize(10%))
+          }
+          // Capa de oscurecimiento para legibilidad (`overlay-opacity` del tema);
+          // las slides de imagen la muestran sin velo
+          #if kind != "image" { scrim(st, default: 90%) }
         ]
       ]
     }
 
     // ─── Contenido ──────────────────────────────────────────────
 
-    // Slide 1: PORTADA (si hay título)
-    if i == 0 and title != "" {
+    if kind == "cover" {
+      // PORTADA
       place(center + horizon)[
-        #block(width: 100%, height: 100%, inset: spacing.xl)[
+        #block(width: 100%, height: 100%, inset: sp.xl)[
           #set align(center + horizon)
-          #let f = resolve-fonts(t)
 
           // Header Marca
           #place(top + center)[
-            #set text(fill: t.text.transparentize(40%), size: sizes.small, weight: "bold", tracking: 3pt)
+            #set text(fill: st.text.transparentize(40%), size: sizes.small, weight: "bold", tracking: 3pt)
             #upper(brand)
           ]
 
           // Título Principal
           #block(width: 100%)[
-            #set text(fill: t.text, font: f.heading.first(), size: sizes.hero * 1.2, weight: "black")
+            #set text(fill: st.text, font: f.heading.first(), size: sizes.hero * 1.2, weight: weight(st, "heading", "black"))
             #set par(leading: 0.65em)
             #upper(title)
           ]
 
-          #v(spacing.lg)
+          #v(sp.lg)
 
           // Separador
-          #line(length: 60pt, stroke: 4pt + t.primary)
+          #line(length: 60pt, stroke: 4pt + st.primary)
 
-          #v(spacing.lg)
+          #v(sp.lg)
 
-          // Hook / Subtítulo (primer slide content)
+          // Hook / Subtítulo (contenido de la slide)
           #block(width: 85%)[
-            #set text(fill: t.text.transparentize(20%), size: sizes.subtitle, weight: "medium")
-            #slide
+            #set text(fill: st.text.transparentize(20%), size: sizes.subtitle, weight: "medium")
+            #body
           ]
 
           // Indicador "Swipe"
           #place(bottom + center)[
-            #text(fill: t.primary, size: sizes.small, weight: "bold", tracking: 2pt)[DESLIZA →]
+            #text(fill: st.primary, size: sizes.small, weight: "bold", tracking: 2pt)[DESLIZA →]
           ]
         ]
       ]
-    } else {
-      // Slide 2+: CONTENIDO
+    } else if kind == "quote" {
+      // CITA: texto grande y centrado entre comillas
+      place(center + horizon)[
+        #block(width: 100%, height: 100%, inset: sp.xl)[
+          #set align(center + horizon)
+
+          #text(fill: st.primary, font: f.heading.first(), size: sizes.stat-xl, weight: "black")[“]
+
+          #block(width: 90%)[
+            #set text(fill: st.text, font: f.heading.first(), size: sizes.title, style: "italic", weight: weight(st, "heading", "bold"))
+            #set par(leading: 0.7em)
+            #body
+          ]
+
+          #v(sp.lg)
+          #line(length: 40pt, stroke: 3pt + st.accent)
+        ]
+      ]
+    } else if kind == "image" {
+      // IMAGEN: la foto a sangre, con el texto como pie
+      if body != "" {
+        place(bottom + left, dx: sp.md, dy: -sp.xxl)[
+          #block(
+            width: 75%,
+            fill: st.surface.transparentize(15%),
+            inset: sp.sm,
+            radius: corner-radius(st),
+          )[
+            #set text(fill: st.text, font: f.body.first(), size: sizes.body, weight: weight(st, "body", "medium"))
+            #body
+          ]
+        ]
+      }
+    } else if kind == "content" {
+      // CONTENIDO
       place(center + horizon)[
         #block(
           width: 100%,
           height: 100%,
-          inset: (x: spacing.xl, y: spacing.xxl), // Más margen vertical
+          inset: (x: sp.xl, y: sp.xxl), // Más margen vertical
         )[
-          #let f = resolve-fonts(t)
           #set align(left + horizon)
-          #set text(fill: t.text, font: f.heading.first(), size: sizes.title * 0.9)
+          #set text(fill: st.text, font: f.heading.first(), size: sizes.title * 0.9)
           #set par(leading: 0.8em)
 
           // Header discreto
           #place(top + left)[
-            #set text(fill: t.text.transparentize(60%), size: sizes.small, weight: "bold", tracking: 2pt)
+            #set text(fill: st.text.transparentize(60%), size: sizes.small, weight: "bold", tracking: 2pt)
             #upper(title)
           ]
 
           // Contenido principal
-          #slide
+          #body
         ]
       ]
+    } else {
+      panic("Layout de slide desconocido: " + kind)
+    }
 
-      // Footer: Numeración
-      place(bottom + right, dx: -spacing.md, dy: -spacing.md)[
-        block(
-        fill: t.surface,
-        inset: (x: 12pt, y: 6pt),
-        radius: 4pt,
+    // Footer: Numeración (salvo en la portada)
+    if kind != "cover" {
+      place(bottom + right, dx: -sp.md, dy: -sp.md)[
+        #block(
+          fill: st.surface,
+          inset: (x: 12pt, y: 6pt),
+          radius: corner-radius(st),
         )[
-        #set text(size: sizes.small, weight: "bold", fill: t.muted)
-        #(i + 1) / #total
+          #set text(size: sizes.small, weight: "bold", fill: st.muted)
+          #(i + 1) / #total
         ]
       ]
     }
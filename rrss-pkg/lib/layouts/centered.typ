@@ -11,7 +11,7 @@
 /// - bg-image (content): Imagen de fondo opcional
 /// - overlay (content): Capa sobre la imagen opcional
 /// - align-val (alignment): Alineación del contenido (default: center + horizon)
-/// - inset-val (length): Padding interno (default: spacing.xl)
+/// - inset-val (length, auto): Padding interno (auto: spacing.xl del tema)
 /// - body (content): Contenido a centrar
 /// -> content
 #let base-card(
@@ -19,25 +19,27 @@
   bg-image: none,
   overlay: none,
   align-val: center + horizon,
-  inset-val: spacing.xl,
+  inset-val: auto,
   body,
 ) = {
+  let inset-val = if inset-val == auto { resolve-spacing(t).xl } else { inset-val }
   set align(align-val)
   block(
     width: 100%,
     height: 100%,
     clip: true,
-    fill: t.bg,
+    fill: bg-fill(t),
   )[
     // Capa 1: Background
     #if bg-image != none {
       place(center + horizon)[#bg-image]
     }
 
-    // Capa 2: Overlay
+    // Capa 2: Overlay (y la capa `overlay-opacity` del tema)
     #if overlay != none {
       place(center + horizon)[#overlay]
     }
+    #place(center + horizon)[#scrim(t)]
 
     // Capa 3: Content Block
     #place(align-val)[
@@ -23,6 +23,7 @@
   overlay: none,
   ..args,
 ) = {
+  let sp = resolve-spacing(t)
   base-card(
     t,
     bg-image: bg-image,
@@ -30,26 +31,26 @@
   )[
     #if tag != none {
       block(
-        inset: (x: spacing.md, y: spacing.xs),
-        radius: 4pt,
+        inset: (x: sp.md, y: sp.xs),
+        radius: corner-radius(t),
         fill: t.primary,
       )[
         #set text(size: sizes.caption, weight: "bold", fill: white)
         #tag
       ]
-      v(spacing.md)
+      v(sp.md)
     }
 
     #block(width: 85%)[
-      #set text(size: sizes.hero, weight: "black", fill: t.text)
+      #set text(size: sizes.hero, weight: weight(t, "heading", "black"), fill: t.text)
       #set par(leading: 0.75em)
       #title
     ]
 
-    #v(spacing.md)
+    #v(sp.md)
 
     #block(width: 75%)[
-      #set text(size: sizes.subtitle, fill: t.muted, weight: "regular")
+      #set text(size: sizes.subtitle, fill: t.muted, weight: weight(t, "body", "regular"))
       #subtitle
     ]
   ]
@@ -23,6 +23,7 @@
   overlay: none,
   ..args,
 ) = {
+  let sp = resolve-spacing(t)
   base-card(
     t,
     bg-image: bg-image,
@@ -31,27 +32,27 @@
     // Comilla decorativa de apertura
     #text(size: 5em, fill: t.primary, weight: "black")["]
 
-    #v(-spacing.lg)
+    #v(-sp.lg)
 
     // Texto de la cita
     #block(width: 85%)[
-      #set text(size: sizes.subtitle, weight: "medium", style: "italic")
+      #set text(size: sizes.subtitle, weight: weight(t, "body", "medium"), style: "italic")
       #set par(leading: 0.85em)
       #quote-text
     ]
 
-    #v(spacing.lg)
+    #v(sp.lg)
 
     // Línea decorativa
     #line(length: 4em, stroke: 0.15em + t.primary)
 
-    #v(spacing.md)
+    #v(sp.md)
 
     // Autor
     #text(size: sizes.body, weight: "bold", fill: t.primary)[#author]
 
     #if source != none {
-      v(spacing.xs)
+      v(sp.xs)
       text(size: sizes.caption, fill: t.muted)[#source]
     }
   ]
@@ -12,10 +12,11 @@
 /// - ratio (array): Proporción de las columnas (default 1fr, 1fr)
 /// -> content
 #let split(t, left, right, ratio: (1fr, 1fr)) = {
+  let sp = resolve-spacing(t)
   block(
     width: 100%,
     height: 100%,
-    fill: t.bg,
+    fill: bg-fill(t),
   )[
     #grid(
       columns: ratio,
@@ -23,8 +24,8 @@
       block(
         width: 100%,
         height: 100%,
-        inset: spacing.lg,
-        fill: t.bg,
+        inset: sp.lg,
+        fill: bg-fill(t),
       )[
         #set align(horizon)
         #left
@@ -32,7 +33,7 @@
       block(
         width: 100%,
         height: 100%,
-        inset: spacing.lg,
+        inset: sp.lg,
         fill: t.surface,
       )[
         #set align(center + horizon)
@@ -23,6 +23,7 @@
   overlay: none,
   ..args,
 ) = {
+  let sp = resolve-spacing(t)
   base-card(
     t,
     bg-image: bg-image,
@@ -31,12 +32,12 @@
     // Número grande
     #text(
       size: sizes.stat,
-      weight: "black",
+      weight: weight(t, "heading", "black"),
       fill: t.primary,
       number,
     )
 
-    #v(spacing.sm)
+    #v(sp.sm)
 
     // Etiqueta
     #block(width: 80%)[
@@ -45,7 +46,7 @@
     ]
 
     #if source != none {
-      v(spacing.md)
+      v(sp.md)
       set text(size: sizes.caption, fill: t.muted)
       [— #source]
     }
@@ -66,6 +66,45 @@
   )
 }
 
+// ─── Roles de estilo del tema ─────────────────────────────────────────────────
+// Opcionales: la CLI solo los incluye en `t` si el tema los define.
+
+/// Relleno de fondo: el degradado del tema (`bg-gradient`) o su color `bg`.
+/// - t (dictionary): Diccionario del tema.
+/// -> color | gradient
+#let bg-fill(t) = t.at("bg-gradient", default: t.bg)
+
+/// Radio de las esquinas de badges y tarjetas (`radius`, 4pt por defecto).
+/// - t (dictionary): Diccionario del tema.
+/// -> length
+#let corner-radius(t) = t.at("radius", default: 4pt)
+
+/// Peso tipográfico de un rol (`weight-heading` o `weight-body`).
+/// - t (dictionary): Diccionario del tema.
+/// - role (str): "heading" o "body".
+/// - default (str, int): Peso que usa el layout si el tema no define uno.
+/// -> str | int
+#let weight(t, role, default) = t.at("weight-" + role, default: default)
+
+/// Escala de espaciado multiplicada por el factor `spacing` del tema.
+/// - t (dictionary): Diccionario del tema.
+/// -> dictionary
+#let resolve-spacing(t) = {
+  let factor = t.at("spacing", default: 1)
+  for (key, value) in spacing { ((key): value * factor) }
+}
+
+/// Capa del color `bg` entre la imagen y el texto, con la opacidad `overlay-opacity`.
+/// - t (dictionary): Diccionario del tema.
+/// - default (ratio, none): Opacidad si el tema no la define (none: sin capa).
+/// -> content
+#let scrim(t, default: none) = {
+  let opacity = t.at("overlay-opacity", default: default)
+  if opacity != none {
+    block(width: 100%, height: 100%, fill: t.bg.transparentize(100% - opacity))
+  }
+}
+
 // ─── Función de Configuración de Dimensiones ──────────────────────────────────
 
 /// Configura las dimensiones y propiedades de la página para el post.
@@ -82,7 +121,7 @@
     width: dims.width,
     height: dims.height,
     margin: 0pt,
-    fill: theme.bg,
+    fill: bg-fill(theme),
   )
 
   set text(